@@ -1,4 +1,11 @@
-use db_rs::server::Server;
+use db_rs::{
+    config::{Config, ServerConfig},
+    registry::CommandRegistry,
+    server::Server,
+    snapshot,
+    store::Store,
+};
+use std::time::Duration;
 
 /// Create and start an instance of KVDB Server.
 #[tokio::main]
@@ -9,9 +16,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     .parse()
     .unwrap();
+    // Data is kept in memory only, unless a directory to write snapshots into is given.
+    let persistence = std::env::args().nth(2).map(|path| Config {
+        path,
+        snapshot_interval: Duration::from_secs(60),
+        archive: None,
+        archive_retention: None,
+    });
+    let store = match &persistence {
+        Some(config) => snapshot::load(&config.path)?,
+        None => Store::new(),
+    };
 
     eprintln!("Server starting on {}", addr);
-    Server::start(addr).await?;
+    Server::start(
+        addr,
+        ServerConfig {
+            persistence,
+            ..Default::default()
+        },
+        store,
+        CommandRegistry::new(),
+    )
+    .await?;
 
     Ok(())
 }
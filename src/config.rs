@@ -1,5 +1,61 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use crate::object_store::ObjectStore;
+
 /// Configurations pertaining to persistant storage.
 pub struct Config {
     /// Path to directory where LSTM pages and value logs can be written to and accessed from.
     pub path: String,
+    /// Time between snapshots of the in-memory store, written to path.
+    pub snapshot_interval: Duration,
+    /// Where a copy of every snapshot is archived to, e.g. for offsite backups.
+    pub archive: Option<Arc<dyn ObjectStore + Send + Sync>>,
+    /// Number of archived snapshots kept, older ones are deleted once a new one is
+    /// archived, the latest always being kept. All are kept if None.
+    pub archive_retention: Option<usize>,
+}
+
+/// Configurations pertaining to the network server.
+pub struct ServerConfig {
+    /// Number of requests that are allowed to execute on the store concurrently.
+    pub workers: usize,
+    /// Number of requests that may wait for a worker, requests beyond this
+    /// are rejected as BUSY rather than queued without bounds.
+    pub queue: usize,
+    /// Time a request may wait for a worker, then for the store lock, and then iterate
+    /// over keys, as KEYS, SCAN, DBHASH and ANALYZE do, before it is cancelled with a
+    /// Timeout error. Other commands always run to completion once executing.
+    pub timeout: Duration,
+    /// Rules deciding which keys are deleted for being too old.
+    pub retention: Vec<RetentionRule>,
+    /// Time between runs of the background task enforcing retention rules.
+    pub retention_interval: Duration,
+    /// Where and how often the store is snapshotted, it lives only in memory if None.
+    pub persistence: Option<Config>,
+    /// Names of commands the server refuses to execute, e.g. `analyze` or custom
+    /// commands, so that expensive or dangerous ones can be locked down. Built-in
+    /// commands are named as in server::ADMISSION, which lists the statements each
+    /// covers, the server failing to start on names that are neither.
+    pub disabled: HashSet<String>,
+}
+
+/// Keys starting with prefix, that haven't been written to for longer than
+/// max_age, are deleted by the server.
+pub struct RetentionRule {
+    pub prefix: String,
+    pub max_age: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            queue: 1024,
+            timeout: Duration::from_secs(5),
+            retention: vec![],
+            retention_interval: Duration::from_secs(60),
+            persistence: None,
+            disabled: HashSet::new(),
+        }
+    }
 }
@@ -0,0 +1,226 @@
+use std::io::BufRead;
+
+/// Reads CSV records from reader one at a time, a line at a time, as per RFC 4180.
+/// Fields may be quoted, in which case they can hold commas, newlines and quotes
+/// written twice (`""`). Lines may end with `\n` or `\r\n`, empty lines are skipped.
+pub struct Records<R> {
+    reader: R,
+    line: String,
+    /// Number of records read so far.
+    count: usize,
+    /// Whether the end of input, or an error, was reached.
+    done: bool,
+}
+
+impl<R: BufRead> Records<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            count: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<Vec<String>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.to_string()));
+                }
+            }
+            let mut chars = self.line.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (quoted, c) {
+                    (true, '"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    (true, '"') => quoted = false,
+                    (true, _) => field.push(c),
+                    (false, '"') if field.is_empty() => quoted = true,
+                    (false, ',') => record.push(std::mem::take(&mut field)),
+                    (false, '\r') if chars.peek() == Some(&'\n') => {}
+                    (false, '\n') => {
+                        if !record.is_empty() || !field.is_empty() {
+                            record.push(field);
+                            self.count += 1;
+                            return Some(Ok(record));
+                        }
+                    }
+                    (false, _) => field.push(c),
+                }
+            }
+        }
+
+        self.done = true;
+        if quoted {
+            return Some(Err(format!(
+                "unterminated quote in record {}",
+                self.count + 1
+            )));
+        }
+        if !record.is_empty() || !field.is_empty() {
+            record.push(field);
+            self.count += 1;
+            return Some(Ok(record));
+        }
+
+        None
+    }
+}
+
+/// Parses CSV text into records of fields, see Records.
+pub fn read_records(text: &str) -> Result<Vec<Vec<String>>, String> {
+    Records::new(text.as_bytes()).collect()
+}
+
+/// Builds a string from a record, as described by spec. Spec is either the name of a
+/// column, e.g. `id`, or a template naming columns within braces, e.g. `user:{id}`.
+pub fn render(spec: &str, header: &[String], record: &[String]) -> Result<String, String> {
+    let field = |name: &str| match header.iter().position(|column| column == name) {
+        Some(i) => Ok(record.get(i).map_or("", String::as_str)),
+        None => Err(format!("no column named `{}`", name)),
+    };
+    if !spec.contains('{') {
+        return field(spec).map(str::to_string);
+    }
+
+    let mut rendered = String::new();
+    let mut rest = spec;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => return Err(format!("unterminated `{{` in `{}`", spec)),
+        };
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(field(&rest[start + 1..end])?);
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// Maps each CSV record read from reader to a key-value pair, as described by
+/// the key and value specs, see render(). The first record names the columns.
+pub struct Rows<R> {
+    records: Records<R>,
+    header: Vec<String>,
+    key: String,
+    value: String,
+}
+
+impl<R: BufRead> Rows<R> {
+    /// Reads the header from reader, failing if it can't be read.
+    pub fn new(reader: R, key: &str, value: &str) -> Result<Self, String> {
+        let mut records = Records::new(reader);
+        let header = records.next().transpose()?.unwrap_or_default();
+
+        Ok(Self {
+            records,
+            header,
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for Rows<R> {
+    type Item = Result<(String, String), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        let row = match (
+            render(&self.key, &self.header, &record),
+            render(&self.value, &self.header, &record),
+        ) {
+            (Ok(key), Ok(value)) => Ok((key, value)),
+            (Err(e), _) | (_, Err(e)) => Err(format!("record {}: {}", self.records.count, e)),
+        };
+
+        Some(row)
+    }
+}
+
+/// Maps each record of CSV text to a key-value pair, see Rows.
+pub fn rows(text: &str, key: &str, value: &str) -> Result<Vec<(String, String)>, String> {
+    Rows::new(text.as_bytes(), key, value)?.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_quoted_records() {
+        let records =
+            read_records("id,bio\r\n1,\"likes \"\"tea\"\", cake\nand naps\"\n\n2,\n").unwrap();
+        assert_eq!(
+            records,
+            vec![
+                vec!["id".to_string(), "bio".to_string()],
+                vec!["1".to_string(), "likes \"tea\", cake\nand naps".to_string()],
+                vec!["2".to_string(), "".to_string()],
+            ]
+        );
+        assert!(read_records("id\n\"1").is_err());
+    }
+
+    #[test]
+    fn test_rows_from_columns_and_templates() {
+        let text = "id,name\n1,alice\n2,bob";
+        assert_eq!(
+            rows(text, "user:{id}", "name").unwrap(),
+            vec![
+                ("user:1".to_string(), "alice".to_string()),
+                ("user:2".to_string(), "bob".to_string()),
+            ]
+        );
+        assert_eq!(
+            rows(text, "id", "{name} ({id})").unwrap()[1],
+            ("2".to_string(), "bob (2)".to_string())
+        );
+        assert!(rows(text, "user:{uid}", "name").is_err());
+        assert!(rows(text, "user:{id", "name").is_err());
+    }
+
+    #[test]
+    fn test_streaming_rows() {
+        let reader =
+            std::io::BufReader::with_capacity(4, "id,name\n1,\"ali\nce\"\n2,{x\n".as_bytes());
+        let mut rows = Rows::new(reader, "user:{id}", "name").unwrap();
+        assert_eq!(
+            rows.next(),
+            Some(Ok(("user:1".to_string(), "ali\nce".to_string())))
+        );
+        assert_eq!(
+            rows.next(),
+            Some(Ok(("user:2".to_string(), "{x".to_string())))
+        );
+        assert_eq!(rows.next(), None);
+        let mut rows = Rows::new("id\n1\n".as_bytes(), "{uid}", "id").unwrap();
+        assert_eq!(
+            rows.next(),
+            Some(Err("record 2: no column named `uid`".to_string()))
+        );
+    }
+}
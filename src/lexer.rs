@@ -0,0 +1,207 @@
+use std::{ops::Range, str::CharIndices};
+
+/// Kinds of tokens user input is divided into.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum TokenKind {
+    /// A word written as is.
+    Bare,
+    /// A word holding quoted text, which is never read as a keyword.
+    Quoted,
+}
+
+/// A token of user input, along with where it was found.
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The word, with quotes removed and escape sequences replaced.
+    pub text: String,
+    /// Byte range of the word in the input, quotes included.
+    pub span: Range<usize>,
+}
+
+/// Describes why user input couldn't be divided into tokens.
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct LexError {
+    pub message: String,
+    /// Byte range of the input at fault.
+    pub span: Range<usize>,
+}
+
+/// Divides user input into tokens, separated by single spaces or tabs. Text within
+/// single or double quotes is part of one token, spaces and tabs included, so that
+/// keys and values holding them can be written. The escape sequences `\n`, `\t`,
+/// `\"`, `\'` and `\\` are replaced by the characters they stand for, while any other
+/// `\` is kept as is, along with the character following it, e.g. in `C:\Users`.
+/// Fails on unterminated quotes, after which no tokens follow.
+pub struct Lexer<'a> {
+    cmd: &'a str,
+    chars: CharIndices<'a>,
+    /// Byte offset the next token starts at.
+    start: usize,
+    /// Whether the end of input, or an error, was reached.
+    done: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(cmd: &'a str) -> Self {
+        Self {
+            cmd,
+            chars: cmd.char_indices(),
+            start: 0,
+            done: false,
+        }
+    }
+
+    /// Ends the token stream with an error about span.
+    fn fail(&mut self, message: String, span: Range<usize>) -> Option<Result<Token, LexError>> {
+        self.done = true;
+        Some(Err(LexError { message, span }))
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let start = self.start;
+        let (mut kind, mut text, mut quote) = (TokenKind::Bare, String::new(), None);
+        while let Some((i, c)) = self.chars.next() {
+            match (quote, c) {
+                (_, '\\') => match self.chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, e @ '"')) | Some((_, e @ '\'')) | Some((_, e @ '\\')) => text.push(e),
+                    Some((_, e)) => {
+                        text.push('\\');
+                        text.push(e);
+                    }
+                    None => text.push('\\'),
+                },
+                (None, '"') | (None, '\'') => {
+                    quote = Some((c, i));
+                    kind = TokenKind::Quoted;
+                }
+                (Some((q, _)), _) if q == c => quote = None,
+                (None, ' ') | (None, '\t') => {
+                    self.start = i + 1;
+                    return Some(Ok(Token {
+                        kind,
+                        text,
+                        span: start..i,
+                    }));
+                }
+                _ => text.push(c),
+            }
+        }
+        if let Some((q, i)) = quote {
+            return self.fail(format!("unterminated quote `{}`", q), i..self.cmd.len());
+        }
+
+        self.done = true;
+        Some(Ok(Token {
+            kind,
+            text,
+            span: start..self.cmd.len(),
+        }))
+    }
+}
+
+/// Divides all of cmd into tokens, see Lexer.
+pub fn tokenize(cmd: &str) -> Result<Vec<Token>, LexError> {
+    Lexer::new(cmd).collect()
+}
+
+/// Divides user input into statements separated by `;`, trimming spaces and tabs around
+/// them and leaving out empty ones. Separators within quotes, or following `\`, are part
+/// of a statement, as they are of a token.
+pub fn split_statements(cmd: &str) -> Vec<&str> {
+    let (mut statements, mut start, mut quote) = (vec![], 0, None);
+    let mut chars = cmd.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (_, '\\') => {
+                chars.next();
+            }
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, ';') => {
+                statements.push(&cmd[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&cmd[start..]);
+
+    statements
+        .into_iter()
+        .map(|statement| statement.trim_matches(|c| c == ' ' || c == '\t'))
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_carry_kinds_and_spans() {
+        let tokens = tokenize("SET  'my key'\tv\\n").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    kind: TokenKind::Bare,
+                    text: "SET".to_string(),
+                    span: 0..3
+                },
+                Token {
+                    kind: TokenKind::Bare,
+                    text: "".to_string(),
+                    span: 4..4
+                },
+                Token {
+                    kind: TokenKind::Quoted,
+                    text: "my key".to_string(),
+                    span: 5..13
+                },
+                Token {
+                    kind: TokenKind::Bare,
+                    text: "v\n".to_string(),
+                    span: 14..17
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_ends_on_error() {
+        let mut lexer = Lexer::new("GET \"key");
+        assert!(matches!(lexer.next(), Some(Ok(_))));
+        assert_eq!(
+            lexer.next(),
+            Some(Err(LexError {
+                message: "unterminated quote `\"`".to_string(),
+                span: 4..8
+            }))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn test_splitting_statements() {
+        assert_eq!(
+            split_statements("SET a 1; SET b 'x;y' ;GET a;"),
+            vec!["SET a 1", "SET b 'x;y'", "GET a"]
+        );
+        assert_eq!(split_statements("GET \\;a ;; "), vec!["GET \\;a"]);
+        assert!(split_statements(" ; ").is_empty());
+    }
+}
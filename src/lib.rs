@@ -3,19 +3,37 @@
 /// operational use with the storage engine.
 pub mod parser;
 
+/// Divides commands passed to the repl into tokens, which the parser reads.
+pub mod lexer;
+
 /// Interfaces to operate on the Storage Engine.
 /// 1. code pertaining to environment management of a REPL.
 pub mod repl;
 /// 2. code pertaining to environment management of a network server.
 pub mod server;
 
+/// Matches keys against glob patterns.
+pub mod pattern;
+
+/// Reads CSV files into key-value pairs that can be imported.
+pub mod import;
+
 /// Code related to the heart of a database, the storage engine.
 pub mod store;
 
+/// Lets embedders extend the server with custom commands.
+pub mod registry;
+
 /// Defines an experimental LSMT based persistant storage API.
 pub mod lsmt;
 
-/// Defines available persistant storage configurations.
+/// Persists the in-memory store through periodic snapshots.
+pub mod snapshot;
+
+/// Interfaces to object storage that archives can be written to.
+pub mod object_store;
+
+/// Defines available persistant storage and server configurations.
 pub mod config;
 
 /// Code generated by tonic for gRPC.
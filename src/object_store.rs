@@ -0,0 +1,126 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// A flat namespace of named blobs, that archives such as snapshots can be written
+/// to. Implement this to keep archives in remote object storage, e.g. S3.
+pub trait ObjectStore {
+    /// Stores data under name, replacing any object already under it.
+    fn put(&self, name: &str, data: &[u8]) -> io::Result<()>;
+    /// Returns the data stored under name.
+    fn get(&self, name: &str) -> io::Result<Vec<u8>>;
+    /// Lists the names of all stored objects.
+    fn list(&self) -> io::Result<Vec<String>>;
+    /// Removes the object stored under name.
+    fn delete(&self, name: &str) -> io::Result<()>;
+}
+
+/// Suffix of files being written, which aren't yet objects.
+const PENDING: &str = ".tmp";
+
+/// Keeps objects as files in a directory.
+pub struct FsObjectStore {
+    root: PathBuf,
+}
+
+impl FsObjectStore {
+    /// Creates an object store in the directory at root, creating it if needed. Files
+    /// left pending by a crash while an object was being written are deleted.
+    pub fn new<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+        fs::create_dir_all(&root)?;
+        for entry in fs::read_dir(&root)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') && name.ends_with(PENDING) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(Self {
+            root: root.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Path of the file holding the object under name, rejecting names
+    /// that would address files outside of root.
+    fn path(&self, name: &str) -> io::Result<PathBuf> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid object name",
+            ));
+        }
+
+        Ok(self.root.join(name))
+    }
+}
+
+impl ObjectStore for FsObjectStore {
+    fn put(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        // Objects are only replaced once completely written and synced to disk.
+        let path = self.path(name)?;
+        let pending = self.root.join(format!(".{}{}", name, PENDING));
+        let mut file = fs::File::create(&pending)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(pending, path)
+    }
+
+    fn get(&self, name: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path(name)?)
+    }
+
+    fn list(&self) -> io::Result<Vec<String>> {
+        let mut names = vec![];
+        for entry in fs::read_dir(&self.root)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if !name.starts_with('.') {
+                names.push(name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn delete(&self, name: &str) -> io::Result<()> {
+        fs::remove_file(self.path(name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fs_object_store_flow() {
+        let root = std::env::temp_dir().join(format!("kvdb-objects-{}", std::process::id()));
+        let objects = FsObjectStore::new(&root).unwrap();
+
+        objects.put("snapshot-1", b"data").unwrap();
+        objects.put("snapshot-1", b"newer data").unwrap();
+        assert_eq!(objects.get("snapshot-1").unwrap(), b"newer data");
+        assert_eq!(objects.list().unwrap(), vec!["snapshot-1".to_string()]);
+
+        objects.delete("snapshot-1").unwrap();
+        assert!(objects.get("snapshot-1").is_err());
+        assert!(objects.list().unwrap().is_empty());
+
+        assert!(objects.put("../escape", b"data").is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_fs_object_store_removes_pending_files() {
+        let root = std::env::temp_dir().join(format!("kvdb-pending-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(".snapshot-1.tmp"), b"partial").unwrap();
+
+        let objects = FsObjectStore::new(&root).unwrap();
+        assert!(!root.join(".snapshot-1.tmp").exists());
+        objects.put("snapshot-2", b"data").unwrap();
+        assert_eq!(objects.list().unwrap(), vec!["snapshot-2".to_string()]);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,29 +1,86 @@
-/// There are 3 types of statement in KVDB, GET/SET/DEL.
-#[derive(PartialEq)]
+use std::{fmt, ops::Range};
+
+use crate::{
+    lexer::{split_statements, tokenize, LexError, TokenKind},
+    store::MAX_SCAN_COUNT,
+};
+
+/// There are 26 types of statement in KVDB, GET/SET/DEL/STAT/ANALYZE/DBHASH/IMPORT/GENERATE/
+/// MGET/MSET/KEYS/SCAN/INCR/DECR/INCRBY/APPEND/RENAME/RENAMENX/GETSET/GETDEL/TYPE/COPY/STRLEN/
+/// DBSIZE/FLUSHALL/HELP.
+#[derive(Clone, Copy, PartialEq)]
 #[cfg_attr(test, derive(Debug))]
 pub enum StatementType {
     /// Relates to the set() method of the Storage Engine.
     Set,
     /// Relates to the get() method of the Storage Engine.
     Get,
-    /// Relates to the del() method of the Storage Engine.
+    /// Relates to the del_many() method of the Storage Engine.
     Del,
-    /// No such operation exists.
-    Unk,
-    /// The parser has failed to understand what the user wants
-    /// to do. Parsing has failed due to wrong command syntax.
-    Fail,
+    /// Relates to the stat() method of the Storage Engine.
+    Stat,
+    /// Relates to the analyze_prefixes() method of the Storage Engine.
+    Analyze,
+    /// Relates to the digest() method of the Storage Engine.
+    DbHash,
+    /// Reads key-value pairs from a file, written with the set_many() method of the Storage Engine.
+    Import,
+    /// Writes pairs built from templates, with the set_many() method of the Storage Engine.
+    Generate,
+    /// Relates to the get_many() method of the Storage Engine.
+    MGet,
+    /// Relates to the set_many() method of the Storage Engine.
+    MSet,
+    /// Relates to the keys() method of the Storage Engine.
+    Keys,
+    /// Relates to the scan() method of the Storage Engine.
+    Scan,
+    /// Relates to the incr_by() method of the Storage Engine, adding 1.
+    Incr,
+    /// Relates to the incr_by() method of the Storage Engine, subtracting 1.
+    Decr,
+    /// Relates to the incr_by() method of the Storage Engine.
+    IncrBy,
+    /// Relates to the append() method of the Storage Engine.
+    Append,
+    /// Relates to the rename() method of the Storage Engine, replacing the target's value.
+    Rename,
+    /// Relates to the rename() method of the Storage Engine, failing on targets in use.
+    RenameNx,
+    /// Relates to the set() method of the Storage Engine, returning the value replaced.
+    GetSet,
+    /// Relates to the take() method of the Storage Engine.
+    GetDel,
+    /// Relates to the type_of() method of the Storage Engine.
+    Type,
+    /// Relates to the copy() method of the Storage Engine.
+    Copy,
+    /// Relates to the value_len() method of the Storage Engine.
+    StrLen,
+    /// Relates to the len() method of the Storage Engine.
+    DbSize,
+    /// Relates to the clear() method of the Storage Engine.
+    FlushAll,
+    /// Describes statements from the command table, without reaching the server.
+    Help,
 }
 
 impl StatementType {
-    /// Convert written operation keywords into enum symbols.
-    fn check(word: &str) -> Self {
-        match word.to_lowercase().as_ref() {
-            "set" | "put" | "insert" | "in" | "i" => Self::Set,
-            "get" | "select" | "output" | "out" | "o" => Self::Get,
-            "del" | "delete" | "rem" | "remove" | "rm" | "d" => Self::Del,
-            _ => Self::Unk,
-        }
+    /// Convert written operation keywords into enum symbols, None if no such operation exists.
+    pub(crate) fn check(word: &str) -> Option<Self> {
+        let word = word.to_lowercase();
+        COMMANDS
+            .iter()
+            .find(|usage| usage.keywords.contains(&word.as_str()))
+            .map(|usage| usage.stype)
+    }
+
+    /// The entry describing this type of statement in the command table.
+    pub fn usage(&self) -> &'static Usage {
+        COMMANDS
+            .iter()
+            .find(|usage| usage.stype == *self)
+            .expect("every type of statement is in the command table")
     }
 
     /// Get string form of command words from the StatementType object.
@@ -32,98 +89,1000 @@ impl StatementType {
             Self::Set => "SET".to_string(),
             Self::Get => "GET".to_string(),
             Self::Del => "DEL".to_string(),
-            _ => "Unknown".to_string(),
+            Self::Stat => "STAT".to_string(),
+            Self::Analyze => "ANALYZE".to_string(),
+            Self::DbHash => "DBHASH".to_string(),
+            Self::Import => "IMPORT".to_string(),
+            Self::Generate => "GENERATE".to_string(),
+            Self::MGet => "MGET".to_string(),
+            Self::MSet => "MSET".to_string(),
+            Self::Keys => "KEYS".to_string(),
+            Self::Scan => "SCAN".to_string(),
+            Self::Incr => "INCR".to_string(),
+            Self::Decr => "DECR".to_string(),
+            Self::IncrBy => "INCRBY".to_string(),
+            Self::Append => "APPEND".to_string(),
+            Self::Rename => "RENAME".to_string(),
+            Self::RenameNx => "RENAMENX".to_string(),
+            Self::GetSet => "GETSET".to_string(),
+            Self::GetDel => "GETDEL".to_string(),
+            Self::Type => "TYPE".to_string(),
+            Self::Copy => "COPY".to_string(),
+            Self::StrLen => "STRLEN".to_string(),
+            Self::DbSize => "DBSIZE".to_string(),
+            Self::FlushAll => "FLUSHALL".to_string(),
+            Self::Help => "HELP".to_string(),
+        }
+    }
+
+    /// Whether statements of this type operate on a key.
+    fn needs_key(&self) -> bool {
+        matches!(
+            self,
+            Self::Set
+                | Self::Get
+                | Self::Stat
+                | Self::Incr
+                | Self::Decr
+                | Self::IncrBy
+                | Self::Append
+                | Self::Rename
+                | Self::RenameNx
+                | Self::GetSet
+                | Self::GetDel
+                | Self::Type
+                | Self::Copy
+                | Self::StrLen
+        )
+    }
+}
+
+/// How a type of statement is written, as output by HELP.
+#[cfg_attr(test, derive(Debug))]
+pub struct Usage {
+    pub stype: StatementType,
+    /// Words the statement may start with, the first being its name.
+    pub keywords: &'static [&'static str],
+    pub syntax: &'static str,
+    pub summary: &'static str,
+    pub example: &'static str,
+}
+
+/// Every type of statement, in the order HELP lists them.
+pub const COMMANDS: &[Usage] = &[
+    Usage {
+        stype: StatementType::Get,
+        keywords: &["get", "select", "output", "out", "o"],
+        syntax: "GET <key> [RANGE <start> <len> | LEN | HASH <algorithm>]",
+        summary: "Outputs the value associated with a key, or a part, the length or a hash of it.",
+        example: "GET user:1 RANGE 0 16",
+    },
+    Usage {
+        stype: StatementType::Set,
+        keywords: &["set", "put", "insert", "in", "i"],
+        syntax: "SET <key> <value> [NX|XX|IFVERSION <token>] [GET] [ID <id>]",
+        summary: "Stores a key-value pair, replacing any value the key is associated with.",
+        example: "SET user:1 alice NX",
+    },
+    Usage {
+        stype: StatementType::Del,
+        keywords: &["del", "delete", "rem", "remove", "rm", "d"],
+        syntax: "DEL <key> [<key> ...]",
+        summary: "Deletes the key-value pairs of all keys and outputs how many were deleted.",
+        example: "DEL user:1 user:2",
+    },
+    Usage {
+        stype: StatementType::Stat,
+        keywords: &["stat"],
+        syntax: "STAT <key>",
+        summary: "Outputs when a key was created and updated, its reads and version token.",
+        example: "STAT user:1",
+    },
+    Usage {
+        stype: StatementType::Analyze,
+        keywords: &["analyze"],
+        syntax: "ANALYZE PREFIXES [<depth>]",
+        summary: "Outputs the number and size of keys, grouped by key prefix.",
+        example: "ANALYZE PREFIXES 2",
+    },
+    Usage {
+        stype: StatementType::DbHash,
+        keywords: &["dbhash"],
+        syntax: "DBHASH",
+        summary: "Outputs a digest of all key-value pairs, the same for identical databases.",
+        example: "DBHASH",
+    },
+    Usage {
+        stype: StatementType::Import,
+        keywords: &["import"],
+        syntax: "IMPORT CSV <path> KEY <column|template> VALUE <column|template> [DRYRUN]",
+        summary: "Stores a key-value pair per record of a CSV file, in batches as MSET would.",
+        example: "IMPORT CSV users.csv KEY user:{id} VALUE name",
+    },
+    Usage {
+        stype: StatementType::Generate,
+        keywords: &["generate"],
+        syntax: "GENERATE <count> <key template> <value template>",
+        summary: "Stores key-value pairs built from templates, {i} being their number.",
+        example: "GENERATE 10000 key:{i} value:{i}",
+    },
+    Usage {
+        stype: StatementType::MGet,
+        keywords: &["mget"],
+        syntax: "MGET <key> [<key> ...]",
+        summary: "Outputs the values associated with all keys, one per line.",
+        example: "MGET user:1 user:2",
+    },
+    Usage {
+        stype: StatementType::MSet,
+        keywords: &["mset"],
+        syntax: "MSET <key> <value> [<key> <value> ...]",
+        summary: "Stores all key-value pairs at once, or none if any key is in use.",
+        example: "MSET user:1 alice user:2 bob",
+    },
+    Usage {
+        stype: StatementType::Keys,
+        keywords: &["keys"],
+        syntax: "KEYS <pattern>",
+        summary: "Outputs all keys matching a glob pattern.",
+        example: "KEYS user:*",
+    },
+    Usage {
+        stype: StatementType::Scan,
+        keywords: &["scan"],
+        syntax: "SCAN <cursor> [MATCH <pattern>] [COUNT <count>]",
+        summary: "Outputs a page of keys matching a glob pattern and the cursor to the next.",
+        example: "SCAN 0 MATCH user:* COUNT 100",
+    },
+    Usage {
+        stype: StatementType::Incr,
+        keywords: &["incr"],
+        syntax: "INCR <key>",
+        summary: "Adds 1 to the integer value associated with a key and outputs the result.",
+        example: "INCR hits",
+    },
+    Usage {
+        stype: StatementType::Decr,
+        keywords: &["decr"],
+        syntax: "DECR <key>",
+        summary: "Subtracts 1 from the integer value associated with a key.",
+        example: "DECR hits",
+    },
+    Usage {
+        stype: StatementType::IncrBy,
+        keywords: &["incrby"],
+        syntax: "INCRBY <key> <increment>",
+        summary: "Adds an integer to the integer value associated with a key.",
+        example: "INCRBY hits 10",
+    },
+    Usage {
+        stype: StatementType::Append,
+        keywords: &["append"],
+        syntax: "APPEND <key> <suffix>",
+        summary: "Appends a suffix to the value associated with a key and outputs its length.",
+        example: "APPEND log:1 ,login",
+    },
+    Usage {
+        stype: StatementType::Rename,
+        keywords: &["rename", "mv"],
+        syntax: "RENAME <key> <newkey>",
+        summary: "Moves the value associated with a key to another, replacing its value.",
+        example: "RENAME user:1 user:100",
+    },
+    Usage {
+        stype: StatementType::RenameNx,
+        keywords: &["renamenx"],
+        syntax: "RENAMENX <key> <newkey>",
+        summary: "Moves the value associated with a key to another not in use.",
+        example: "RENAMENX user:1 user:100",
+    },
+    Usage {
+        stype: StatementType::GetSet,
+        keywords: &["getset"],
+        syntax: "GETSET <key> <value>",
+        summary: "Stores a key-value pair and outputs the value it replaced.",
+        example: "GETSET user:1 alice",
+    },
+    Usage {
+        stype: StatementType::GetDel,
+        keywords: &["getdel"],
+        syntax: "GETDEL <key>",
+        summary: "Deletes the key-value pair of a key and outputs the value deleted.",
+        example: "GETDEL session:1",
+    },
+    Usage {
+        stype: StatementType::Type,
+        keywords: &["type"],
+        syntax: "TYPE <key>",
+        summary: "Outputs the type of the value associated with a key, int or string.",
+        example: "TYPE hits",
+    },
+    Usage {
+        stype: StatementType::Copy,
+        keywords: &["copy", "cp"],
+        syntax: "COPY <key> <newkey> [REPLACE]",
+        summary: "Stores a copy of the value associated with a key under another.",
+        example: "COPY user:1 user:1:draft",
+    },
+    Usage {
+        stype: StatementType::StrLen,
+        keywords: &["strlen"],
+        syntax: "STRLEN <key>",
+        summary: "Outputs the length in bytes of the value associated with a key.",
+        example: "STRLEN user:1",
+    },
+    Usage {
+        stype: StatementType::DbSize,
+        keywords: &["dbsize"],
+        syntax: "DBSIZE",
+        summary: "Outputs the number of keys stored in the database.",
+        example: "DBSIZE",
+    },
+    Usage {
+        stype: StatementType::FlushAll,
+        keywords: &["flushall"],
+        syntax: "FLUSHALL [ASYNC]",
+        summary: "Deletes all key-value pairs and outputs how many were deleted.",
+        example: "FLUSHALL ASYNC",
+    },
+    Usage {
+        stype: StatementType::Help,
+        keywords: &["help"],
+        syntax: "HELP [<operation>]",
+        summary: "Lists all operations, or describes how to write one of them.",
+        example: "HELP SET",
+    },
+];
+
+/// Server-side transformations that can be applied to a value on GET,
+/// so that only the required part of a large value is transferred.
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum GetModifier {
+    /// `GET <key> RANGE <start> <len>`, a slice of the value.
+    Range(u64, u64),
+    /// `GET <key> LEN`, the length of the value in bytes.
+    Len,
+    /// `GET <key> HASH <algorithm>`, a digest of the value.
+    Hash(HashAlgorithm),
+}
+
+/// Algorithms values can be hashed with by GET HASH.
+#[derive(PartialEq, Clone, Copy)]
+#[cfg_attr(test, derive(Debug))]
+pub enum HashAlgorithm {
+    /// `fnv1a64`, 64-bit FNV-1a as used by DBHASH, which isn't cryptographic.
+    Fnv1a64,
+}
+
+impl HashAlgorithm {
+    /// All algorithms, in the order they are listed to users.
+    pub const ALL: &'static [Self] = &[Self::Fnv1a64];
+
+    /// Name of the algorithm, as written in statements and sent to the server.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fnv1a64 => "fnv1a64",
+        }
+    }
+
+    /// The algorithm named name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl GetModifier {
+    /// Parse modifier words following the key of a GET statement. Returns `None`
+    /// if the words don't describe a modifier and `Some(Err(..))` if they start
+    /// with a modifier keyword but its arguments are malformed.
+    fn check(words: &[&str]) -> Option<Result<Self, String>> {
+        match words.first()?.to_lowercase().as_ref() {
+            "range" => match words[1..] {
+                [start, len] => match (start.parse(), len.parse()) {
+                    (Ok(start), Ok(len)) => Some(Ok(Self::Range(start, len))),
+                    _ => Some(Err(
+                        "`RANGE` expects unsigned integers <start> <len>".to_string()
+                    )),
+                },
+                _ => Some(Err("`RANGE` expects exactly <start> <len>".to_string())),
+            },
+            "len" => match words.len() {
+                1 => Some(Ok(Self::Len)),
+                _ => Some(Err("`LEN` expects no arguments".to_string())),
+            },
+            "hash" => {
+                match words[1..] {
+                    [name] => Some(HashAlgorithm::from_name(name).map(Self::Hash).ok_or_else(
+                        || {
+                            let names: Vec<_> =
+                                HashAlgorithm::ALL.iter().map(|a| a.name()).collect();
+                            format!(
+                                "`HASH` doesn't support `{}`, expected one of: {}",
+                                name,
+                                names.join(", ")
+                            )
+                        },
+                    )),
+                    _ => Some(Err("`HASH` expects exactly <algorithm>".to_string())),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Conditions under which a SET statement is allowed to write.
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum SetCondition {
+    /// `SET <key> <value> IFVERSION <token>`, replace the value only if its
+    /// current version token, as returned by GET, is still the same.
+    IfVersion(u64),
+    /// `SET <key> <value> NX`, write only if the key isn't associated with a value.
+    IfAbsent,
+    /// `SET <key> <value> XX`, write only if the key is associated with a value.
+    IfPresent,
+}
+
+impl SetCondition {
+    /// Parse the option ending a SET statement. Returns `None` if the
+    /// words don't describe a condition and `Some(Err(..))` if they start
+    /// with a condition keyword but its argument is malformed.
+    fn check(words: &[&str]) -> Option<Result<Self, String>> {
+        match words {
+            [keyword] if keyword.to_lowercase() == "nx" => Some(Ok(Self::IfAbsent)),
+            [keyword] if keyword.to_lowercase() == "xx" => Some(Ok(Self::IfPresent)),
+            [keyword, token] if keyword.to_lowercase() == "ifversion" => match token.parse() {
+                Ok(token) => Some(Ok(Self::IfVersion(token))),
+                Err(_) => Some(Err(
+                    "`IFVERSION` expects an unsigned integer <token>".to_string()
+                )),
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Describes how rows of a CSV file are imported as key-value pairs.
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Import {
+    /// Path to the CSV file, which is read by the client.
+    pub path: String,
+    /// Column or template keys are built from, e.g. `user:{id}`.
+    pub key: String,
+    /// Column or template values are built from.
+    pub value: String,
+    /// Whether the pairs are only output, rather than written.
+    pub dry_run: bool,
+}
+
+/// Describes how many key-value pairs are generated, e.g. for load tests, and how.
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Generate {
+    /// Number of pairs to generate.
+    pub count: u64,
+    /// Template keys are built from, e.g. `key:{i}`.
+    pub key: String,
+    /// Template values are built from.
+    pub value: String,
+}
+
+impl Generate {
+    /// Builds the i-th pair, replacing `{i}` in the templates with i.
+    pub fn pair(&self, i: u64) -> (String, String) {
+        let i = i.to_string();
+        (self.key.replace("{i}", &i), self.value.replace("{i}", &i))
+    }
+}
+
+/// Describes which page of keys a scan returns.
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Scan {
+    /// Where the scan continues from, `0` to start one.
+    pub cursor: String,
+    /// Glob pattern returned keys match, `*` unless given.
+    pub pattern: String,
+    /// Number of keys returned at most, 10 unless given.
+    pub count: u64,
+}
+
+/// Number of single character edits needed to turn a into b.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + (ca != *cb) as usize);
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The operation word was most likely a typo of, if any.
+fn suggest(word: &str) -> Option<String> {
+    let word = word.to_lowercase();
+    COMMANDS
+        .iter()
+        .map(|usage| usage.keywords[0])
+        .map(|op| (edit_distance(&word, op), op))
+        .filter(|(distance, _)| *distance <= 2 && *distance < word.chars().count())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, op)| op.to_uppercase())
+}
+
+/// What is wrong with a statement that fails to parse.
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    /// The input couldn't be divided into words, e.g. due to an unterminated quote.
+    Syntax(String),
+    /// The first word isn't a known operation, though it may name a custom command,
+    /// along with the operation it is closest to, in case it is a typo.
+    UnknownCommand(String, Option<String>),
+    /// The operation needs a key, which wasn't provided.
+    MissingKey(String),
+    /// The operation needs a value, which wasn't provided.
+    MissingValue(String),
+    /// Inputs to the operation are malformed, as described.
+    InvalidArgument(String, String),
+}
+
+/// Describes why a statement fails to parse and which part of the input is at fault.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Byte range of the input at fault, empty at its end if something is missing.
+    pub span: Range<usize>,
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+
+    /// Marks the part of cmd at fault with carets, to be printed beneath cmd.
+    pub fn underline(&self, cmd: &str) -> String {
+        let start = cmd[..self.span.start].chars().count();
+        let len = cmd[self.span.clone()].chars().count().max(1);
+        format!("{}{}", " ".repeat(start), "^".repeat(len))
+    }
+}
+
+impl From<LexError> for ParseError {
+    fn from(e: LexError) -> Self {
+        Self::new(ParseErrorKind::Syntax(e.message), e.span)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::Syntax(e) => write!(f, "Statement ignored, {}", e),
+            ParseErrorKind::UnknownCommand(word, None) => {
+                write!(f, "Unknown operation `{}`", word)
+            }
+            ParseErrorKind::UnknownCommand(word, Some(op)) => {
+                write!(f, "Unknown operation `{}`, did you mean `{}`?", word, op)
+            }
+            ParseErrorKind::MissingKey(op) => {
+                write!(f, "`{}` operation ignored, KEY not provided", op)
+            }
+            ParseErrorKind::MissingValue(op) => {
+                write!(f, "`{}` operation ignored, VALUE not provided", op)
+            }
+            ParseErrorKind::InvalidArgument(op, e) => {
+                write!(f, "`{}` operation ignored, {}", op, e)
+            }
         }
     }
 }
 
+impl std::error::Error for ParseError {}
+
 /// Describes the structure of a REPL statement.
 #[derive(PartialEq)]
 #[cfg_attr(test, derive(Debug))]
 pub struct Statement {
     /// Depicts the type of Operation the statement conveys.
     pub stype: StatementType,
-    /// The key variable, only used in get/set/del statements.
+    /// The key variable, used in all statements operating on a single key.
     pub key: Option<String>,
-    /// The value variable, only used in set statements.
+    /// Keys operated on all at once, only used in del/mget statements.
+    pub keys: Vec<String>,
+    /// Key-value pairs written all at once, only used in mset statements.
+    pub pairs: Vec<(String, String)>,
+    /// The value variable, only used in set/append/getset statements.
     pub value: Option<String>,
+    /// Transformation applied to the value, only used in get statements.
+    pub modifier: Option<GetModifier>,
+    /// Condition for the write to happen, only used in set statements.
+    pub condition: Option<SetCondition>,
+    /// Client supplied ID of the write, that makes retrying it safe, only used in set statements.
+    pub id: Option<String>,
+    /// Whether the value replaced is output, only used in set statements.
+    pub previous: bool,
+    /// Number of delimiters keys are grouped till, only used in analyze statements.
+    pub depth: Option<usize>,
+    /// What to import and how, only used in import statements.
+    pub import: Option<Import>,
+    /// What to generate and how, only used in generate statements.
+    pub generate: Option<Generate>,
+    /// Glob pattern keys are matched against, only used in keys statements.
+    pub pattern: Option<String>,
+    /// Which page of keys to return, only used in scan statements.
+    pub scan: Option<Scan>,
+    /// What to add to the integer value, only used in incr/decr/incrby statements.
+    pub delta: Option<i64>,
+    /// The key to move or copy the value to, only used in rename/renamenx/copy statements.
+    pub target: Option<String>,
+    /// Whether the value of the target key is replaced, only used in copy statements.
+    pub replace: bool,
+    /// Whether memory is freed in the background, only used in flushall statements.
+    pub background: bool,
+    /// The type of statement to describe, only used in help statements.
+    pub topic: Option<StatementType>,
+    /// Inputs that were ignored, which the user should be warned about.
+    pub warnings: Vec<String>,
 }
 
 impl Statement {
-    /// Creates a REPL statement from user input command.
-    pub fn prep(cmd: &String) -> Self {
+    /// Creates a REPL statement from each statement of a script, separated by `;`, along
+    /// with the text it was written as, which the spans of parse errors are relative to.
+    pub fn parse_script(cmd: &str) -> Vec<(&str, Result<Self, ParseError>)> {
+        split_statements(cmd)
+            .into_iter()
+            .map(|text| (text, Self::prep(text)))
+            .collect()
+    }
+
+    /// Creates a REPL statement from user input command, or describes why it can't be.
+    pub fn prep(cmd: &str) -> Result<Self, ParseError> {
         // Divide user input into words.
-        let cmd_words: Vec<&str> = cmd.split(|c| c == ' ' || c == '\t').collect();
+        let words = tokenize(cmd)?;
+        let cmd_words: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        // Byte range of the input covering a range of words, its end if there are none.
+        let span = |range: Range<usize>| match range.start < range.end.min(words.len()) {
+            true => words[range.start].span.start..words[range.end.min(words.len()) - 1].span.end,
+            false => cmd.len()..cmd.len(),
+        };
         // Find statement type.
-        let stype = StatementType::check(cmd_words[0]);
-        // Collect rest of the words, if exists, into a single string.
+        let stype = match StatementType::check(cmd_words[0]) {
+            Some(stype) => stype,
+            None => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnknownCommand(cmd_words[0].to_string(), suggest(cmd_words[0])),
+                    span(0..1),
+                ))
+            }
+        };
+        let invalid = |e: &str, range| {
+            ParseError::new(
+                ParseErrorKind::InvalidArgument(stype.get_word(), e.to_string()),
+                span(range),
+            )
+        };
+
+        // A SET statement may end with a condition, i.e. `NX`, `XX` or `IFVERSION <token>`,
+        // `ID <id>` and/or `GET`, in any order, these words aren't part of the value. At
+        // least one word of value is kept, as are options quoted to be read as part of it.
+        let (mut condition, mut id, mut previous) = (None, None, false);
+        let mut val_end = cmd_words.len();
+        while stype == StatementType::Set && val_end > 3 {
+            // Options are a keyword, followed by an argument for all but `GET`, `NX` and `XX`.
+            let len = match cmd_words[val_end - 1].to_lowercase().as_ref() {
+                "get" | "nx" | "xx" => 1,
+                _ => 2,
+            };
+            if val_end - len < 3 || words[val_end - len].kind != TokenKind::Bare {
+                break;
+            }
+            let option = &cmd_words[val_end - len..val_end];
+            if len == 1 && option[0].to_lowercase() == "get" && !previous {
+                previous = true;
+            } else if option[0].to_lowercase() == "id" && id.is_none() {
+                id = Some(option[1].to_string());
+            } else if condition.is_none() {
+                match SetCondition::check(option) {
+                    Some(c) => {
+                        condition = Some(c.map_err(|e| invalid(&e, val_end - len..val_end))?)
+                    }
+                    None => break,
+                }
+            } else {
+                break;
+            }
+            val_end -= len;
+        }
+        // Values replaced through IFVERSION aren't returned.
+        if previous && matches!(condition, Some(SetCondition::IfVersion(_))) {
+            return Err(invalid(
+                "`GET` can't be combined with `IFVERSION`",
+                val_end..cmd_words.len(),
+            ));
+        }
+        // Collect rest of the words, if exists, into a single string. Empty words left
+        // around the value by repeated spaces are dropped, spaces within quotes are kept.
         let cmd_val = match cmd_words.len() > 1 {
-            true => cmd_words[2..].to_vec().join(" ").trim().to_string(),
+            true => {
+                let val_words = &cmd_words[2..val_end];
+                let start = val_words
+                    .iter()
+                    .position(|w| !w.is_empty())
+                    .unwrap_or(val_words.len());
+                let end = val_words
+                    .iter()
+                    .rposition(|w| !w.is_empty())
+                    .map_or(start, |i| i + 1);
+                val_words[start..end].join(" ")
+            }
             false => "".to_string(),
         };
 
         // The first word after the operation keyword is supposed to be
         // the statement key, else the statement has failed to parse.
-        let key = match stype {
-            StatementType::Get | StatementType::Set | StatementType::Del => {
-                if cmd_words.len() < 2 {
-                    // Incase the user forgets to input required options
-                    // for an operation, fail by setting None.
-                    eprintln!(
-                        "Error: `{}` operation ignored, KEY not provided.",
-                        stype.get_word()
-                    );
-                    None
-                } else {
-                    Some(cmd_words[1].to_string())
+        let key = match stype.needs_key() {
+            true => match cmd_words.get(1) {
+                Some(key) => Some(key.to_string()),
+                None => {
+                    return Err(ParseError::new(
+                        ParseErrorKind::MissingKey(stype.get_word()),
+                        span(1..2),
+                    ))
                 }
+            },
+            false => None,
+        };
+        // Every word after the operation keyword of a DEL, MGET or MSET statement is a key
+        // or value to operate on, except for empty words left by repeated spaces.
+        let takes_args = matches!(
+            stype,
+            StatementType::Del | StatementType::MGet | StatementType::MSet
+        );
+        let args: Vec<String> = match takes_args {
+            true => words[1..]
+                .iter()
+                .filter(|w| w.kind == TokenKind::Quoted || !w.text.is_empty())
+                .map(|w| w.text.clone())
+                .collect(),
+            false => vec![],
+        };
+        if takes_args && args.is_empty() {
+            return Err(ParseError::new(
+                ParseErrorKind::MissingKey(stype.get_word()),
+                span(1..2),
+            ));
+        }
+        // MSET expects keys and values taking turns, every key needing a value.
+        let (keys, pairs) = match stype {
+            StatementType::Del | StatementType::MGet => (args, vec![]),
+            StatementType::MSet if args.len() % 2 == 1 => {
+                return Err(ParseError::new(
+                    ParseErrorKind::MissingValue(stype.get_word()),
+                    cmd.len()..cmd.len(),
+                ))
             }
-            _ => None,
+            StatementType::MSet => (
+                vec![],
+                args.chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect(),
+            ),
+            _ => (vec![], vec![]),
         };
+        let mut warnings = vec![];
 
         // The string after the operation keyword and the statement key
-        // is the statement value. Parsing should fail if no such value
-        // for the `set` operation. Currently, the code sets value to an
-        // empty string value.
+        // is the statement value, which `set`, `append` and `getset` operations can't do without.
         let value = match stype {
-            StatementType::Set => {
-                if cmd_words.len() < 3 {
-                    // Incase the user forgets to input required options
-                    // for an operation, fail by setting None.
-                    eprintln!(
-                        "Error: `{}` operation ignored, VALUE not provided.",
-                        stype.get_word()
-                    );
-                    None
-                } else {
-                    Some(cmd_val)
+            StatementType::Set | StatementType::Append | StatementType::GetSet => {
+                match cmd_words.len() < 3 {
+                    true => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::MissingValue(stype.get_word()),
+                            span(2..3),
+                        ))
+                    }
+                    false => Some(cmd_val),
                 }
             }
-            StatementType::Get | StatementType::Del => {
+            StatementType::Stat
+            | StatementType::GetDel
+            | StatementType::Type
+            | StatementType::StrLen => {
                 if cmd_words.len() > 2 {
-                    // Incase the user unnecessarily inputs a value for either
-                    // GET or DEL operations, warn them and don't use the value.
-                    eprintln!("Warning: Too many inputs, `{}` was ignored.", cmd_val);
+                    // Incase the user unnecessarily inputs a value for STAT, GETDEL,
+                    // TYPE or STRLEN operations, warn them and don't use the value.
+                    warnings.push(format!("Too many inputs, `{}` was ignored", cmd_val));
+                }
+                None
+            }
+            StatementType::DbHash | StatementType::DbSize => {
+                if cmd_words.len() > 1 {
+                    // DBHASH and DBSIZE take no inputs at all.
+                    warnings.push(format!(
+                        "Too many inputs, `{}` was ignored",
+                        cmd_words[1..].join(" ")
+                    ));
                 }
                 None
             }
             _ => None,
         };
 
-        // Quick Fix to #1. If for most operations key is set to None and for set operation only,
-        // if value is set to None, set stype to Fail to fail parsing. All Unk operations are passed as is.
-        if (stype == StatementType::Set && value.is_none())
-            || (stype != StatementType::Unk && key.is_none())
-        {
-            // Fail state, when user forgets to pass necessary inputs.
-            Self {
-                stype: StatementType::Fail,
-                key: None,
-                value: None,
+        // Words following the key of a GET statement may describe a modifier,
+        // anything else is ignored with a warning, as with DEL.
+        let modifier = match stype {
+            StatementType::Get if cmd_words.len() > 2 => {
+                match GetModifier::check(&cmd_words[2..]) {
+                    Some(modifier) => Some(modifier.map_err(|e| invalid(&e, 2..cmd_words.len()))?),
+                    None => {
+                        warnings.push(format!(
+                            "Too many inputs, `{}` was ignored",
+                            cmd_words[2..].join(" ")
+                        ));
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // An ANALYZE statement names what to analyze, currently only PREFIXES,
+        // optionally followed by the depth to group keys by.
+        let depth = match stype {
+            StatementType::Analyze => match cmd_words.get(1).map(|w| w.to_lowercase()) {
+                Some(ref sub) if sub == "prefixes" => match cmd_words.get(2) {
+                    None => Some(1),
+                    Some(depth) => match depth.parse() {
+                        Ok(depth) => {
+                            if cmd_words.len() > 3 {
+                                warnings.push(format!(
+                                    "Too many inputs, `{}` was ignored",
+                                    cmd_words[3..].join(" ")
+                                ));
+                            }
+                            Some(depth)
+                        }
+                        Err(_) => {
+                            return Err(invalid(
+                                "`PREFIXES` expects an unsigned integer <depth>",
+                                2..3,
+                            ))
+                        }
+                    },
+                },
+                _ => return Err(invalid("expected `PREFIXES`", 1..2)),
+            },
+            _ => None,
+        };
+
+        // An IMPORT statement names the format of the file, currently only CSV, its path
+        // and how keys and values are built from its columns, optionally as a dry run.
+        let import = match stype {
+            StatementType::Import => match cmd_words[1..] {
+                [format, path, key_word, key, value_word, value, ref rest @ ..]
+                    if format.to_lowercase() == "csv"
+                        && key_word.to_lowercase() == "key"
+                        && value_word.to_lowercase() == "value" =>
+                {
+                    let dry_run = match rest {
+                        [] => false,
+                        [dry_run] if dry_run.to_lowercase() == "dryrun" => true,
+                        _ => return Err(invalid("expected `DRYRUN`", 7..cmd_words.len())),
+                    };
+                    Some(Import {
+                        path: path.to_string(),
+                        key: key.to_string(),
+                        value: value.to_string(),
+                        dry_run,
+                    })
+                }
+                _ => return Err(invalid(
+                    "expected `CSV <path> KEY <column|template> VALUE <column|template> [DRYRUN]`",
+                    1..cmd_words.len(),
+                )),
+            },
+            _ => None,
+        };
+
+        // A GENERATE statement writes <count> pairs, built from templates in which
+        // `{i}` stands for the number of the pair, counting from 0.
+        let generate = match stype {
+            StatementType::Generate => match cmd_words[1..] {
+                [count, key, value, ref rest @ ..] => match count.parse() {
+                    Ok(count) => {
+                        if !rest.is_empty() {
+                            warnings
+                                .push(format!("Too many inputs, `{}` was ignored", rest.join(" ")));
+                        }
+                        Some(Generate {
+                            count,
+                            key: key.to_string(),
+                            value: value.to_string(),
+                        })
+                    }
+                    Err(_) => return Err(invalid("expected an unsigned integer <count>", 1..2)),
+                },
+                _ => {
+                    return Err(invalid(
+                        "expected `<count> <key template> <value template>`",
+                        1..cmd_words.len(),
+                    ))
+                }
+            },
+            _ => None,
+        };
+
+        // A KEYS statement lists keys matching a glob pattern, which quotes allow
+        // to hold spaces, to list all keys the pattern is `*`.
+        let pattern = match stype {
+            StatementType::Keys => match cmd_words.get(1) {
+                Some(pattern) => {
+                    if cmd_words.len() > 2 {
+                        warnings.push(format!(
+                            "Too many inputs, `{}` was ignored",
+                            cmd_words[2..].join(" ")
+                        ));
+                    }
+                    Some(pattern.to_string())
+                }
+                None => return Err(invalid("expected <pattern>", 1..2)),
+            },
+            _ => None,
+        };
+
+        // A SCAN statement starts from a cursor, optionally followed by `MATCH <pattern>`
+        // and `COUNT <count>`, in any order.
+        let scan = match stype {
+            StatementType::Scan => match cmd_words.get(1) {
+                Some(cursor) => {
+                    let mut scan = Scan {
+                        cursor: cursor.to_string(),
+                        pattern: "*".to_string(),
+                        count: 10,
+                    };
+                    for i in (2..cmd_words.len()).step_by(2) {
+                        let option = cmd_words[i].to_lowercase();
+                        match (words[i].kind, option.as_ref(), cmd_words.get(i + 1)) {
+                            (TokenKind::Bare, "match", Some(pattern)) => {
+                                scan.pattern = pattern.to_string()
+                            }
+                            (TokenKind::Bare, "count", Some(count)) => match count.parse() {
+                                Ok(count) if count > 0 && count <= MAX_SCAN_COUNT as u64 => {
+                                    scan.count = count
+                                }
+                                _ => {
+                                    let e = format!(
+                                        "`COUNT` expects a positive integer <count> of at most {}",
+                                        MAX_SCAN_COUNT
+                                    );
+                                    return Err(invalid(&e, i + 1..i + 2));
+                                }
+                            },
+                            _ => {
+                                return Err(invalid(
+                                    "expected `MATCH <pattern>` or `COUNT <count>`",
+                                    i..cmd_words.len(),
+                                ))
+                            }
+                        }
+                    }
+                    Some(scan)
+                }
+                None => return Err(invalid("expected <cursor>", 1..2)),
+            },
+            _ => None,
+        };
+
+        // INCR and DECR add 1 and -1 respectively, INCRBY the integer following the key.
+        let (delta, ignored) = match stype {
+            StatementType::Incr => (Some(1), 2),
+            StatementType::Decr => (Some(-1), 2),
+            StatementType::IncrBy => match cmd_words.get(2).map(|delta| delta.parse()) {
+                Some(Ok(delta)) => (Some(delta), 3),
+                _ => return Err(invalid("expected an integer <increment>", 2..3)),
+            },
+            _ => (None, 0),
+        };
+        if delta.is_some() && cmd_words.len() > ignored {
+            warnings.push(format!(
+                "Too many inputs, `{}` was ignored",
+                cmd_words[ignored..].join(" ")
+            ));
+        }
+
+        // A RENAME, RENAMENX or COPY statement names the key to move or copy the value
+        // to after the key, COPY may then be told to `REPLACE` the value of that key.
+        let replace = stype == StatementType::Copy
+            && cmd_words.len() > 3
+            && words[3].kind == TokenKind::Bare
+            && cmd_words[3].to_lowercase() == "replace";
+        let target = match stype {
+            StatementType::Rename | StatementType::RenameNx | StatementType::Copy => {
+                match cmd_words.get(2) {
+                    Some(target) => {
+                        let ignored = if replace { 4 } else { 3 };
+                        if cmd_words.len() > ignored {
+                            warnings.push(format!(
+                                "Too many inputs, `{}` was ignored",
+                                cmd_words[ignored..].join(" ")
+                            ));
+                        }
+                        Some(target.to_string())
+                    }
+                    None => return Err(invalid("expected <newkey>", 2..3)),
+                }
+            }
+            _ => None,
+        };
+
+        // A FLUSHALL statement may be told to free memory in the background with `ASYNC`.
+        let background = stype == StatementType::FlushAll
+            && cmd_words.len() > 1
+            && words[1].kind == TokenKind::Bare
+            && cmd_words[1].to_lowercase() == "async";
+        if stype == StatementType::FlushAll {
+            let ignored = if background { 2 } else { 1 };
+            if cmd_words.len() > ignored {
+                warnings.push(format!(
+                    "Too many inputs, `{}` was ignored",
+                    cmd_words[ignored..].join(" ")
+                ));
             }
-        } else {
-            Self { stype, key, value }
         }
+
+        // A HELP statement may name the operation to describe, by any of its keywords.
+        let topic = match (stype, cmd_words.get(1)) {
+            (StatementType::Help, Some(word)) => {
+                if cmd_words.len() > 2 {
+                    warnings.push(format!(
+                        "Too many inputs, `{}` was ignored",
+                        cmd_words[2..].join(" ")
+                    ));
+                }
+                match StatementType::check(word) {
+                    Some(topic) => Some(topic),
+                    None => return Err(invalid(&format!("unknown operation `{}`", word), 1..2)),
+                }
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            stype,
+            key,
+            keys,
+            pairs,
+            value,
+            modifier,
+            condition,
+            id,
+            previous,
+            depth,
+            import,
+            generate,
+            pattern,
+            scan,
+            delta,
+            target,
+            replace,
+            background,
+            topic,
+            warnings,
+        })
     }
 }
 
@@ -137,19 +1096,106 @@ mod statement_prep {
         };
     }
 
+    /// A statement of type stype without any inputs, for tests to fill in.
+    fn empty(stype: StatementType) -> Statement {
+        Statement {
+            stype,
+            key: None,
+            keys: vec![],
+            pairs: vec![],
+            value: None,
+            modifier: None,
+            condition: None,
+            id: None,
+            previous: false,
+            depth: None,
+            import: None,
+            generate: None,
+            pattern: None,
+            scan: None,
+            delta: None,
+            target: None,
+            replace: false,
+            background: false,
+            topic: None,
+            warnings: vec![],
+        }
+    }
+
     #[test]
-    fn test_returns_unk_on_unknown_statement() {
+    fn test_returns_unknown_command_on_unknown_statement() {
         let statement = get_statement!("UNKNOWN_STATEMENT");
         assert_eq!(
             statement,
-            Statement {
-                stype: StatementType::Unk,
-                key: None,
-                value: None
-            }
+            Err(ParseError {
+                kind: ParseErrorKind::UnknownCommand("UNKNOWN_STATEMENT".to_owned(), None),
+                span: 0..17
+            })
+        );
+    }
+
+    #[test]
+    fn test_suggesting_operation_on_typo() {
+        let statement = get_statement!("GTE MY_KEY");
+        assert_eq!(
+            statement,
+            Err(ParseError {
+                kind: ParseErrorKind::UnknownCommand("GTE".to_owned(), Some("GET".to_owned())),
+                span: 0..3
+            })
+        );
+    }
+
+    #[test]
+    fn test_parsing_script() {
+        let statements = Statement::parse_script("SET a 1; GET 'a;b' ;DEL");
+        assert_eq!(
+            statements,
+            vec![
+                (
+                    "SET a 1",
+                    Ok(Statement {
+                        key: Some("a".to_owned()),
+                        value: Some("1".to_owned()),
+                        ..empty(StatementType::Set)
+                    })
+                ),
+                (
+                    "GET 'a;b'",
+                    Ok(Statement {
+                        key: Some("a;b".to_owned()),
+                        ..empty(StatementType::Get)
+                    })
+                ),
+                (
+                    "DEL",
+                    Err(ParseError {
+                        kind: ParseErrorKind::MissingKey("DEL".to_owned()),
+                        span: 3..3
+                    })
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_are_errors() {
+        let error: Box<dyn std::error::Error> = Box::new(Statement::prep("GET").unwrap_err());
+        assert_eq!(
+            error.to_string(),
+            "`GET` operation ignored, KEY not provided"
         );
     }
 
+    #[test]
+    fn test_underlining_span_at_fault() {
+        let cmd = "GET MY_KEY RANGE 2";
+        let error = Statement::prep(cmd).unwrap_err();
+        assert_eq!(error.underline(cmd), "           ^^^^^^^");
+        let error = Statement::prep("GET").unwrap_err();
+        assert_eq!(error.underline("GET"), "   ^");
+    }
+
     mod get {
         use super::*;
 
@@ -158,11 +1204,10 @@ mod statement_prep {
             let statement = get_statement!("GET");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Fail,
-                    key: None,
-                    value: None
-                }
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingKey("GET".to_owned()),
+                    span: 3..3
+                })
             );
         }
 
@@ -171,11 +1216,10 @@ mod statement_prep {
             let statement = get_statement!("GET MY_KEY");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Get,
+                Ok(Statement {
                     key: Some("MY_KEY".to_owned()),
-                    value: None
-                }
+                    ..empty(StatementType::Get)
+                })
             );
         }
 
@@ -184,11 +1228,77 @@ mod statement_prep {
             let statement = get_statement!("GET KEY1 KEY2 KEY3");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Get,
+                Ok(Statement {
                     key: Some("KEY1".to_owned()),
-                    value: None
-                }
+                    warnings: vec!["Too many inputs, `KEY2 KEY3` was ignored".to_owned()],
+                    ..empty(StatementType::Get)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_get_with_range() {
+            let statement = get_statement!("GET MY_KEY RANGE 2 10");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("MY_KEY".to_owned()),
+                    modifier: Some(GetModifier::Range(2, 10)),
+                    ..empty(StatementType::Get)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_get_with_len() {
+            let statement = get_statement!("GET MY_KEY len");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("MY_KEY".to_owned()),
+                    modifier: Some(GetModifier::Len),
+                    ..empty(StatementType::Get)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_get_with_hash() {
+            let statement = get_statement!("GET MY_KEY HASH FNV1A64");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("MY_KEY".to_owned()),
+                    modifier: Some(GetModifier::Hash(HashAlgorithm::Fnv1a64)),
+                    ..empty(StatementType::Get)
+                })
+            );
+
+            let statement = get_statement!("GET MY_KEY HASH sha256");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "GET".to_owned(),
+                        "`HASH` doesn't support `sha256`, expected one of: fnv1a64".to_owned()
+                    ),
+                    span: 11..22
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_get_with_malformed_range() {
+            let statement = get_statement!("GET MY_KEY RANGE 2");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "GET".to_owned(),
+                        "`RANGE` expects exactly <start> <len>".to_owned()
+                    ),
+                    span: 11..18
+                })
             );
         }
     }
@@ -201,11 +1311,10 @@ mod statement_prep {
             let statement = get_statement!("DEL");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Fail,
-                    key: None,
-                    value: None
-                }
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingKey("DEL".to_owned()),
+                    span: 3..3
+                })
             );
         }
 
@@ -214,80 +1323,951 @@ mod statement_prep {
             let statement = get_statement!("DEL MY_KEY");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Del,
-                    key: Some("MY_KEY".to_owned()),
-                    value: None
-                }
+                Ok(Statement {
+                    keys: vec!["MY_KEY".to_owned()],
+                    ..empty(StatementType::Del)
+                })
             );
         }
 
         #[test]
-        fn test_ignoring_multiple_keys_on_del() {
-            let statement = get_statement!("DEL KEY1 KEY2 KEY3");
+        fn test_parsing_del_with_multiple_keys() {
+            let statement = get_statement!("DEL KEY1  KEY2 '' KEY3");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Del,
-                    key: Some("KEY1".to_owned()),
-                    value: None
-                }
+                Ok(Statement {
+                    keys: vec![
+                        "KEY1".to_owned(),
+                        "KEY2".to_owned(),
+                        "".to_owned(),
+                        "KEY3".to_owned()
+                    ],
+                    ..empty(StatementType::Del)
+                })
             );
         }
     }
 
-    mod set {
+    mod mget {
         use super::*;
 
         #[test]
-        fn test_parsing_set_without_key() {
-            let statement = get_statement!("SET");
+        fn test_parsing_mget_without_keys() {
+            let statement = get_statement!("MGET ");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Fail,
-                    key: None,
-                    value: None
-                }
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingKey("MGET".to_owned()),
+                    span: 5..5
+                })
             );
         }
 
         #[test]
-        fn test_parsing_empty_valued_set_statement() {
-            let statement = get_statement!("SET MY_KEY");
+        fn test_parsing_mget_with_multiple_keys() {
+            let statement = get_statement!("mget KEY1 KEY2");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Fail,
-                    key: None,
-                    value: None
-                }
+                Ok(Statement {
+                    keys: vec!["KEY1".to_owned(), "KEY2".to_owned()],
+                    ..empty(StatementType::MGet)
+                })
             );
         }
+    }
+
+    mod mset {
+        use super::*;
 
         #[test]
-        fn test_parsing_proper_set_statement() {
-            let statement = get_statement!("SET MY_KEY MY_VALUE");
+        fn test_parsing_mset_with_pairs() {
+            let statement = get_statement!("MSET KEY1 VALUE1 KEY2 'VALUE 2'");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    pairs: vec![
+                        ("KEY1".to_owned(), "VALUE1".to_owned()),
+                        ("KEY2".to_owned(), "VALUE 2".to_owned())
+                    ],
+                    ..empty(StatementType::MSet)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_mset_with_odd_number_of_words() {
+            let statement = get_statement!("MSET KEY1 VALUE1 KEY2");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingValue("MSET".to_owned()),
+                    span: 21..21
+                })
+            );
+        }
+    }
+
+    mod stat {
+        use super::*;
+
+        #[test]
+        fn test_parsing_stat_without_key() {
+            let statement = get_statement!("STAT");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingKey("STAT".to_owned()),
+                    span: 4..4
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_proper_stat_statement() {
+            let statement = get_statement!("stat MY_KEY");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Set,
+                Ok(Statement {
                     key: Some("MY_KEY".to_owned()),
-                    value: Some("MY_VALUE".to_owned())
-                }
+                    ..empty(StatementType::Stat)
+                })
             );
         }
+    }
+
+    mod analyze {
+        use super::*;
 
         #[test]
-        fn returns_set_considering_all_next_values() {
-            let statement = get_statement!("SET KEY1 VALUE1 VALUE2 VALUE3");
+        fn test_parsing_analyze_prefixes_with_default_depth() {
+            let statement = get_statement!("ANALYZE PREFIXES");
             assert_eq!(
                 statement,
-                Statement {
-                    stype: StatementType::Set,
-                    key: Some("KEY1".to_owned()),
-                    value: Some("VALUE1 VALUE2 VALUE3".to_owned())
-                }
+                Ok(Statement {
+                    depth: Some(1),
+                    ..empty(StatementType::Analyze)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_analyze_prefixes_with_depth() {
+            let statement = get_statement!("analyze prefixes 3");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    depth: Some(3),
+                    ..empty(StatementType::Analyze)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_analyze_without_subcommand() {
+            let statement = get_statement!("ANALYZE");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "ANALYZE".to_owned(),
+                        "expected `PREFIXES`".to_owned()
+                    ),
+                    span: 7..7
+                })
+            );
+        }
+    }
+
+    mod dbhash {
+        use super::*;
+
+        #[test]
+        fn test_parsing_dbhash_statement() {
+            let statement = get_statement!("DBHASH");
+            assert_eq!(statement, Ok(empty(StatementType::DbHash)));
+        }
+    }
+
+    mod dbsize {
+        use super::*;
+
+        #[test]
+        fn test_parsing_dbsize_statement() {
+            let statement = get_statement!("dbsize now");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    warnings: vec!["Too many inputs, `now` was ignored".to_owned()],
+                    ..empty(StatementType::DbSize)
+                })
+            );
+        }
+    }
+
+    mod flushall {
+        use super::*;
+
+        #[test]
+        fn test_parsing_flushall_statements() {
+            let statement = get_statement!("FLUSHALL");
+            assert_eq!(statement, Ok(empty(StatementType::FlushAll)));
+            let statement = get_statement!("flushall async");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    background: true,
+                    ..empty(StatementType::FlushAll)
+                })
+            );
+            let statement = get_statement!("FLUSHALL SYNC");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    warnings: vec!["Too many inputs, `SYNC` was ignored".to_owned()],
+                    ..empty(StatementType::FlushAll)
+                })
+            );
+        }
+    }
+
+    mod help {
+        use super::*;
+
+        #[test]
+        fn test_parsing_help_statements() {
+            let statement = get_statement!("HELP");
+            assert_eq!(statement, Ok(empty(StatementType::Help)));
+            let statement = get_statement!("help mv");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    topic: Some(StatementType::Rename),
+                    ..empty(StatementType::Help)
+                })
+            );
+            let statement = get_statement!("HELP SET NX");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    topic: Some(StatementType::Set),
+                    warnings: vec!["Too many inputs, `NX` was ignored".to_owned()],
+                    ..empty(StatementType::Help)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_help_for_unknown_operation() {
+            let statement = get_statement!("HELP FROB");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "HELP".to_owned(),
+                        "unknown operation `FROB`".to_owned()
+                    ),
+                    span: 5..9
+                })
+            );
+        }
+
+        #[test]
+        fn test_command_table_matches_keywords() {
+            for usage in COMMANDS {
+                assert_eq!(usage.stype.usage().keywords, usage.keywords);
+                assert_eq!(usage.stype.get_word(), usage.keywords[0].to_uppercase());
+                for keyword in usage.keywords {
+                    assert_eq!(StatementType::check(keyword), Some(usage.stype));
+                }
+            }
+        }
+    }
+
+    mod keys {
+        use super::*;
+
+        #[test]
+        fn test_parsing_keys_without_pattern() {
+            let statement = get_statement!("KEYS");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "KEYS".to_owned(),
+                        "expected <pattern>".to_owned()
+                    ),
+                    span: 4..4
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_keys_with_pattern() {
+            let statement = get_statement!("KEYS 'user:* name' extra");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    pattern: Some("user:* name".to_owned()),
+                    warnings: vec!["Too many inputs, `extra` was ignored".to_owned()],
+                    ..empty(StatementType::Keys)
+                })
+            );
+        }
+    }
+
+    mod scan {
+        use super::*;
+
+        #[test]
+        fn test_parsing_scan_with_defaults() {
+            let statement = get_statement!("SCAN 0");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    scan: Some(Scan {
+                        cursor: "0".to_owned(),
+                        pattern: "*".to_owned(),
+                        count: 10
+                    }),
+                    ..empty(StatementType::Scan)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_scan_with_options() {
+            let statement = get_statement!("scan 757365723a31 count 100 MATCH user:*");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    scan: Some(Scan {
+                        cursor: "757365723a31".to_owned(),
+                        pattern: "user:*".to_owned(),
+                        count: 100
+                    }),
+                    ..empty(StatementType::Scan)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_scan_with_malformed_options() {
+            let statement = get_statement!("SCAN 0 COUNT 0");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "SCAN".to_owned(),
+                        "`COUNT` expects a positive integer <count> of at most 10000".to_owned()
+                    ),
+                    span: 13..14
+                })
+            );
+            let statement = get_statement!("SCAN 0 COUNT 1000000000000");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "SCAN".to_owned(),
+                        "`COUNT` expects a positive integer <count> of at most 10000".to_owned()
+                    ),
+                    span: 13..26
+                })
+            );
+            let statement = get_statement!("SCAN 0 MATCH");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "SCAN".to_owned(),
+                        "expected `MATCH <pattern>` or `COUNT <count>`".to_owned()
+                    ),
+                    span: 7..12
+                })
+            );
+        }
+    }
+
+    mod incr {
+        use super::*;
+
+        #[test]
+        fn test_parsing_incr_and_decr() {
+            let statement = get_statement!("INCR hits");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("hits".to_owned()),
+                    delta: Some(1),
+                    ..empty(StatementType::Incr)
+                })
+            );
+            let statement = get_statement!("decr hits 5");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("hits".to_owned()),
+                    delta: Some(-1),
+                    warnings: vec!["Too many inputs, `5` was ignored".to_owned()],
+                    ..empty(StatementType::Decr)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_incrby() {
+            let statement = get_statement!("INCRBY hits -20");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("hits".to_owned()),
+                    delta: Some(-20),
+                    ..empty(StatementType::IncrBy)
+                })
+            );
+            let statement = get_statement!("INCRBY hits many");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "INCRBY".to_owned(),
+                        "expected an integer <increment>".to_owned()
+                    ),
+                    span: 12..16
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_incr_without_key() {
+            let statement = get_statement!("INCR");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingKey("INCR".to_owned()),
+                    span: 4..4
+                })
+            );
+        }
+    }
+
+    mod append {
+        use super::*;
+
+        #[test]
+        fn test_parsing_append_statement() {
+            let statement = get_statement!("APPEND log 'line 1\\n' ID 7");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("log".to_owned()),
+                    value: Some("line 1\n ID 7".to_owned()),
+                    ..empty(StatementType::Append)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_append_without_value() {
+            let statement = get_statement!("APPEND log");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingValue("APPEND".to_owned()),
+                    span: 10..10
+                })
+            );
+        }
+    }
+
+    mod rename {
+        use super::*;
+
+        #[test]
+        fn test_parsing_rename_statements() {
+            let statement = get_statement!("RENAME old new");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("old".to_owned()),
+                    target: Some("new".to_owned()),
+                    ..empty(StatementType::Rename)
+                })
+            );
+            let statement = get_statement!("renamenx old 'new key'");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("old".to_owned()),
+                    target: Some("new key".to_owned()),
+                    ..empty(StatementType::RenameNx)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_rename_without_target() {
+            let statement = get_statement!("RENAME old");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "RENAME".to_owned(),
+                        "expected <newkey>".to_owned()
+                    ),
+                    span: 10..10
+                })
+            );
+        }
+    }
+
+    mod copy {
+        use super::*;
+
+        #[test]
+        fn test_parsing_copy_statements() {
+            let statement = get_statement!("COPY src dst");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("src".to_owned()),
+                    target: Some("dst".to_owned()),
+                    ..empty(StatementType::Copy)
+                })
+            );
+            let statement = get_statement!("copy src dst replace now");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("src".to_owned()),
+                    target: Some("dst".to_owned()),
+                    replace: true,
+                    warnings: vec!["Too many inputs, `now` was ignored".to_owned()],
+                    ..empty(StatementType::Copy)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_copy_with_quoted_replace() {
+            let statement = get_statement!("COPY src dst 'REPLACE'");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("src".to_owned()),
+                    target: Some("dst".to_owned()),
+                    warnings: vec!["Too many inputs, `REPLACE` was ignored".to_owned()],
+                    ..empty(StatementType::Copy)
+                })
+            );
+        }
+    }
+
+    mod getset {
+        use super::*;
+
+        #[test]
+        fn test_parsing_getset_statement() {
+            let statement = get_statement!("GETSET KEY1 VALUE1 NX");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1 NX".to_owned()),
+                    ..empty(StatementType::GetSet)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_getset_without_value() {
+            let statement = get_statement!("GETSET KEY1");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingValue("GETSET".to_owned()),
+                    span: 11..11
+                })
+            );
+        }
+    }
+
+    mod getdel {
+        use super::*;
+
+        #[test]
+        fn test_parsing_getdel_statement() {
+            let statement = get_statement!("GETDEL KEY1 KEY2");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    warnings: vec!["Too many inputs, `KEY2` was ignored".to_owned()],
+                    ..empty(StatementType::GetDel)
+                })
+            );
+        }
+    }
+
+    mod strlen {
+        use super::*;
+
+        #[test]
+        fn test_parsing_strlen_statement() {
+            let statement = get_statement!("STRLEN KEY1 KEY2");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    warnings: vec!["Too many inputs, `KEY2` was ignored".to_owned()],
+                    ..empty(StatementType::StrLen)
+                })
+            );
+        }
+    }
+
+    mod type_of {
+        use super::*;
+
+        #[test]
+        fn test_parsing_type_statement() {
+            let statement = get_statement!("type KEY1");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    ..empty(StatementType::Type)
+                })
+            );
+        }
+    }
+
+    mod import {
+        use super::*;
+
+        #[test]
+        fn test_parsing_import_csv_statement() {
+            let statement = get_statement!("IMPORT CSV users.csv KEY user:{id} VALUE name DRYRUN");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    import: Some(Import {
+                        path: "users.csv".to_owned(),
+                        key: "user:{id}".to_owned(),
+                        value: "name".to_owned(),
+                        dry_run: true
+                    }),
+                    ..empty(StatementType::Import)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_import_without_value() {
+            let statement = get_statement!("IMPORT CSV users.csv KEY id");
+            assert!(matches!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(..),
+                    span
+                }) if span == (7..27)
+            ));
+        }
+    }
+
+    mod generate {
+        use super::*;
+
+        #[test]
+        fn test_parsing_generate_statement() {
+            let statement = get_statement!("GENERATE 10000 key:{i} 'value {i}'");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    generate: Some(Generate {
+                        count: 10000,
+                        key: "key:{i}".to_owned(),
+                        value: "value {i}".to_owned()
+                    }),
+                    ..empty(StatementType::Generate)
+                })
+            );
+            let (key, value) = statement.unwrap().generate.unwrap().pair(42);
+            assert_eq!((key.as_str(), value.as_str()), ("key:42", "value 42"));
+        }
+
+        #[test]
+        fn test_parsing_generate_with_malformed_count() {
+            let statement = get_statement!("GENERATE many key:{i} value:{i}");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "GENERATE".to_owned(),
+                        "expected an unsigned integer <count>".to_owned()
+                    ),
+                    span: 9..13
+                })
+            );
+        }
+    }
+
+    mod set {
+        use super::*;
+
+        #[test]
+        fn test_parsing_set_without_key() {
+            let statement = get_statement!("SET");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingKey("SET".to_owned()),
+                    span: 3..3
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_empty_valued_set_statement() {
+            let statement = get_statement!("SET MY_KEY");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingValue("SET".to_owned()),
+                    span: 10..10
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_proper_set_statement() {
+            let statement = get_statement!("SET MY_KEY MY_VALUE");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("MY_KEY".to_owned()),
+                    value: Some("MY_VALUE".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn returns_set_considering_all_next_values() {
+            let statement = get_statement!("SET KEY1 VALUE1 VALUE2 VALUE3");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1 VALUE2 VALUE3".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_version_condition() {
+            let statement = get_statement!("SET KEY1 VALUE1 VALUE2 IFVERSION 42");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1 VALUE2".to_owned()),
+                    condition: Some(SetCondition::IfVersion(42)),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_id_and_version_condition() {
+            let statement = get_statement!("SET KEY1 VALUE1 IFVERSION 42 ID 1b9d6bcd");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1".to_owned()),
+                    condition: Some(SetCondition::IfVersion(42)),
+                    id: Some("1b9d6bcd".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_flags() {
+            let statement = get_statement!("SET KEY1 VALUE1 nx");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1".to_owned()),
+                    condition: Some(SetCondition::IfAbsent),
+                    ..empty(StatementType::Set)
+                })
+            );
+            let statement = get_statement!("SET KEY1 VALUE1 XX GET ID 1b9d6bcd");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1".to_owned()),
+                    condition: Some(SetCondition::IfPresent),
+                    id: Some("1b9d6bcd".to_owned()),
+                    previous: true,
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_keeps_flags_in_value() {
+            let statement = get_statement!("SET KEY1 GET");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("GET".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+            let statement = get_statement!("SET KEY1 VALUE1 NX XX");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1 NX".to_owned()),
+                    condition: Some(SetCondition::IfPresent),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_get_and_version_condition() {
+            let statement = get_statement!("SET KEY1 VALUE1 IFVERSION 42 GET");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "SET".to_owned(),
+                        "`GET` can't be combined with `IFVERSION`".to_owned()
+                    ),
+                    span: 16..32
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_keeps_value_before_options() {
+            let statement = get_statement!("SET KEY1 ID 1b9d6bcd");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("ID 1b9d6bcd".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_keeps_quoted_options_in_value() {
+            let statement = get_statement!("SET KEY1 VALUE1 'ID' 1b9d6bcd");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("VALUE1 ID 1b9d6bcd".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_quoted_key_and_value() {
+            let statement = get_statement!("SET \"my key\" ' some  value'");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("my key".to_owned()),
+                    value: Some(" some  value".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_quotes_within_quotes() {
+            let statement = get_statement!("SET KEY1 'say \"hi\"' VALUE2");
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("say \"hi\" VALUE2".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_escape_sequences() {
+            let statement = get_statement!(r#"SET KEY1 "line1\nline2\t\"quoted\"" it\'s\\"#);
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("line1\nline2\t\"quoted\" it's\\".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_unknown_escape_sequence() {
+            let statement = get_statement!(r#"SET KEY1 C:\Users\"#);
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    key: Some("KEY1".to_owned()),
+                    value: Some("C:\\Users\\".to_owned()),
+                    ..empty(StatementType::Set)
+                })
+            );
+            let statement = get_statement!(r#"KEYS a\*"#);
+            assert_eq!(
+                statement,
+                Ok(Statement {
+                    pattern: Some("a\\*".to_owned()),
+                    ..empty(StatementType::Keys)
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_unterminated_quote() {
+            let statement = get_statement!("SET \"my key VALUE1");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::Syntax("unterminated quote `\"`".to_owned()),
+                    span: 4..18
+                })
+            );
+        }
+
+        #[test]
+        fn test_parsing_set_with_malformed_version_condition() {
+            let statement = get_statement!("SET KEY1 VALUE1 IFVERSION latest");
+            assert_eq!(
+                statement,
+                Err(ParseError {
+                    kind: ParseErrorKind::InvalidArgument(
+                        "SET".to_owned(),
+                        "`IFVERSION` expects an unsigned integer <token>".to_owned()
+                    ),
+                    span: 16..32
+                })
             );
         }
     }
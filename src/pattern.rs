@@ -0,0 +1,101 @@
+/// Whether text matches the glob pattern, compared byte by byte. In patterns, `*`
+/// matches any number of bytes, `?` matches a single byte, `[abc]` matches one of
+/// the bytes within brackets, `[a-z]` one within the range and `[^a]` or `[!a]` any
+/// byte but those within brackets. Other bytes match themselves, as does any byte
+/// following `\`, e.g. `\*` matches only `*`.
+pub fn matches(pattern: &[u8], text: &[u8]) -> bool {
+    // Where to resume from if matching fails after the last `*`, which is then
+    // made to match one byte more, along with how far into text it matched.
+    let mut backtrack = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(p + 1),
+            Some(b'[') => match_class(&pattern[p + 1..], text[t]).map(|len| p + 1 + len),
+            Some(b'\\') if p + 1 < pattern.len() => match pattern[p + 1] == text[t] {
+                true => Some(p + 2),
+                false => None,
+            },
+            Some(b) if *b == text[t] => Some(p + 1),
+            _ => None,
+        };
+        match (step, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                t += 1;
+            }
+            (None, Some((star, matched))) => {
+                p = star + 1;
+                t = matched + 1;
+                backtrack = Some((star, matched + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    // Text is consumed, so only trailing `*` may be left in pattern.
+    pattern[p..].iter().all(|b| *b == b'*')
+}
+
+/// Matches byte against the class at the start of pattern, following its `[`,
+/// returning the length of the class up to and including its `]` if it matches.
+/// Unterminated classes match nothing.
+fn match_class(pattern: &[u8], byte: u8) -> Option<usize> {
+    // A `]` right after `[` is part of the class, rather than ending it.
+    let end = pattern.iter().skip(1).position(|b| *b == b']')? + 1;
+    let (negated, class) = match pattern[0] {
+        b'^' | b'!' => (true, &pattern[1..end]),
+        _ => (false, &pattern[..end]),
+    };
+
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            found |= class[i] <= byte && byte <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == byte;
+            i += 1;
+        }
+    }
+
+    match found != negated {
+        true => Some(end + 1),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches(b"user:*", b"user:42"));
+        assert!(matches(b"user:*", b"user:"));
+        assert!(!matches(b"user:*", b"session:42"));
+        assert!(matches(b"*:*:name", b"user:42:name"));
+        assert!(!matches(b"*:*:name", b"user:42:email"));
+        assert!(matches(b"h?llo", b"hallo"));
+        assert!(!matches(b"h?llo", b"hllo"));
+        assert!(matches(b"*", b""));
+    }
+
+    #[test]
+    fn test_classes_and_escapes() {
+        assert!(matches(b"h[ae]llo", b"hello"));
+        assert!(!matches(b"h[ae]llo", b"hillo"));
+        assert!(matches(b"h[^e]llo", b"hallo"));
+        assert!(!matches(b"h[!e]llo", b"hello"));
+        assert!(matches(b"key[0-9]", b"key7"));
+        assert!(!matches(b"key[0-9]", b"keyx"));
+        assert!(matches(b"what\\?", b"what?"));
+        assert!(!matches(b"what\\?", b"whats"));
+    }
+}
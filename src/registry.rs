@@ -0,0 +1,80 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use tonic::Status;
+
+use crate::{parser::StatementType, store::Store};
+
+/// Executes a custom command with access to the server's store and the arguments
+/// passed to the command, returning the reply to be sent back to the client.
+pub type Handler =
+    Box<dyn Fn(&mut Store<Bytes, Bytes>, &[Bytes]) -> Result<Bytes, String> + Send + Sync>;
+
+/// A custom command as registered by an embedder.
+struct Command {
+    /// Number of arguments the command expects.
+    arity: usize,
+    handler: Handler,
+}
+
+/// Holds commands registered by embedders, executed by the server on
+/// statements the parser doesn't recognize as built-in operations.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: HashMap<String, Command>,
+}
+
+impl CommandRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a case-insensitive command name, expecting exactly arity arguments.
+    /// Fails if the name is already in use, by a built-in operation or another command.
+    pub fn register<F>(&mut self, name: &str, arity: usize, handler: F) -> Result<(), String>
+    where
+        F: Fn(&mut Store<Bytes, Bytes>, &[Bytes]) -> Result<Bytes, String> + Send + Sync + 'static,
+    {
+        let name = name.to_lowercase();
+        if StatementType::check(&name).is_some() || self.commands.contains_key(&name) {
+            return Err(format!("Command `{}` already exists", name));
+        }
+
+        self.commands.insert(
+            name,
+            Command {
+                arity,
+                handler: Box::new(handler),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Whether a command is registered as the case-insensitive name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands.contains_key(&name.to_lowercase())
+    }
+
+    /// Executes the command registered as name on store with args.
+    pub fn exec(
+        &self,
+        store: &mut Store<Bytes, Bytes>,
+        name: &str,
+        args: &[Bytes],
+    ) -> Result<Bytes, Status> {
+        let cmd = match self.commands.get(&name.to_lowercase()) {
+            Some(cmd) => cmd,
+            None => return Err(Status::not_found("Command not registered")),
+        };
+        if cmd.arity != args.len() {
+            return Err(Status::invalid_argument(format!(
+                "Expected {} arguments, found {}",
+                cmd.arity,
+                args.len()
+            )));
+        }
+
+        (cmd.handler)(store, args).map_err(Status::aborted)
+    }
+}
@@ -1,30 +1,162 @@
+use prost::Message;
 use std::io;
 use std::io::{stdin, BufRead, Write};
-use tonic::{transport::Channel, Request};
+use std::time::{Duration, Instant};
+use tokio::time::{delay_for, interval};
+use tonic::{
+    transport::{self, Channel},
+    Code, Request, Response, Status,
+};
 
 use crate::{
-    kvdb_proto::{kvdb_client::KvdbClient, Byte, KeyValue},
-    parser::{Statement, StatementType},
+    import,
+    kvdb_proto::{
+        kvdb_client::KvdbClient, Byte, Command, ConditionalKeyValue, Depth, Flush, Hash, Hello,
+        Increment, KeyValue, KeyValues, Keys, Null, Range, ReplyMetadata, Scan, SetMode, Source,
+        Transfer, VersionedKeyValue,
+    },
+    lexer::tokenize,
+    parser::{
+        GetModifier, ParseError, ParseErrorKind, SetCondition, Statement, StatementType, COMMANDS,
+    },
+    server::{FEATURES, PROTOCOL_VERSION, REPLY_METADATA},
     store::ExecResult,
 };
 
+/// Attempts made at connecting to the server before giving up.
+const CONNECT_ATTEMPTS: u32 = 6;
+/// Time waited before the first reconnection attempt, doubling with every attempt.
+const CONNECT_BACKOFF: Duration = Duration::from_millis(100);
+/// Time between pings keeping the connection to the server alive.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Number of generated or imported pairs sent to the server per request,
+/// keeping requests well within the size limit of gRPC messages.
+const WRITE_BATCH: u64 = 1000;
+
 /// The REPL struct is used to hold environment variables relating to the REPL.
 pub struct REPL {
     /// User input read from the CLI, in string form.
     cmd: String,
     /// Storage Engine used by the REPL with string based storage.
     store: KvdbClient<Channel>,
+    /// Whether the time taken by each statement is printed along with its result.
+    verbose: bool,
+    /// Whether the last statement failed for the server being unreachable.
+    unavailable: bool,
+    /// Metadata the server attached to replies to the last statement, summed up
+    /// over all requests it took.
+    metadata: Option<ReplyMetadata>,
 }
 
 impl REPL {
     /// Create a new instance of the REPL.
     pub async fn new(addr: String) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut store = Self::connect(addr).await?;
+        // Servers predating HELLO don't implement it, but can still be used.
+        match store
+            .hello(Request::new(Hello {
+                protocol: PROTOCOL_VERSION,
+                client_name: "kvdb-repl".to_string(),
+                features: FEATURES.iter().map(|f| f.to_string()).collect(),
+            }))
+            .await
+        {
+            Ok(res) => {
+                let res = res.into_inner();
+                eprintln!(
+                    "Connected to KVDB v{}, protocol v{}",
+                    res.server_version, res.protocol
+                );
+            }
+            Err(e) if e.code() == Code::Unimplemented => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        tokio::spawn(Self::keep_alive(store.clone()));
+
         Ok(REPL {
             cmd: "".to_owned(),
-            store: KvdbClient::connect(addr).await?,
+            store,
+            verbose: false,
+            unavailable: false,
+            metadata: None,
         })
     }
 
+    /// Delays waited between attempts at reaching the server, CONNECT_ATTEMPTS being
+    /// made in all, starting at CONNECT_BACKOFF and doubling every attempt.
+    fn backoff() -> impl Iterator<Item = Duration> {
+        (0..CONNECT_ATTEMPTS - 1).map(|i| CONNECT_BACKOFF * 2u32.pow(i))
+    }
+
+    /// Connects to the server at addr, retrying with exponential backoff, so that
+    /// the REPL survives servers that are restarting or briefly unreachable.
+    async fn connect(addr: String) -> Result<KvdbClient<Channel>, transport::Error> {
+        for backoff in Self::backoff() {
+            match KvdbClient::connect(addr.clone()).await {
+                Ok(store) => return Ok(store),
+                Err(_) => {
+                    eprintln!("Couldn't connect to {}, retrying in {:?}.", addr, backoff);
+                    delay_for(backoff).await;
+                }
+            }
+        }
+
+        KvdbClient::connect(addr).await
+    }
+
+    /// Waits for the server to be reachable again, with the same backoff as connect(),
+    /// returning whether it is. The channel reconnects on use, so pinging it suffices.
+    async fn reconnect(&mut self) -> bool {
+        for backoff in Self::backoff() {
+            eprintln!("Server unavailable, retrying in {:?}.", backoff);
+            delay_for(backoff).await;
+            if self.store.ping(Request::new(Null {})).await.is_ok() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns the message replied, adding the metadata the server attached to the reply,
+    /// if any, to that of the statement being executed.
+    fn served<T>(&mut self, res: Response<T>) -> T {
+        let attached = res
+            .metadata()
+            .get_bin(REPLY_METADATA)
+            .and_then(|value| value.to_bytes().ok())
+            .and_then(|bytes| ReplyMetadata::decode(bytes).ok());
+        if let Some(attached) = attached {
+            let metadata = self.metadata.get_or_insert_with(ReplyMetadata::default);
+            metadata.elapsed_micros += attached.elapsed_micros;
+            metadata.keys += attached.keys;
+            metadata.bytes_read += attached.bytes_read;
+            metadata.bytes_written += attached.bytes_written;
+            metadata.served_from = attached.served_from;
+        }
+
+        res.into_inner()
+    }
+
+    /// Prints why a request failed, noting whether it was for the server being
+    /// unreachable, in which case the statement may be retried.
+    fn failed(&mut self, e: Status) -> ExecResult {
+        eprintln!("{}", e.message());
+        self.unavailable = e.code() == Code::Unavailable;
+        ExecResult::Failed
+    }
+
+    /// Periodically pings the server, keeping the connection from being dropped
+    /// as idle. Should the connection break, the channel reconnects on next use.
+    async fn keep_alive(mut store: KvdbClient<Channel>) {
+        let mut ticks = interval(KEEPALIVE_INTERVAL);
+        loop {
+            ticks.tick().await;
+            let _ = store.ping(Request::new(Null {})).await;
+        }
+    }
+
     pub async fn start(addr: String) -> Result<(), Box<dyn std::error::Error>> {
         Self::new(addr).await?.repl().await;
 
@@ -58,27 +190,137 @@ impl REPL {
             match MetaCmdResult::run(&self.cmd) {
                 MetaCmdResult::Unrecognized => println!("db: meta command not found: {}", self.cmd),
                 MetaCmdResult::Success => {}
+                MetaCmdResult::Verbose => {
+                    self.verbose = !self.verbose;
+                    println!("Verbose mode {}", if self.verbose { "on" } else { "off" });
+                }
             }
         } else {
-            let st = Statement::prep(&self.cmd);
-            let key = st.key.unwrap_or("".to_string());
-            // If type of statement is legit, execute, else fail.
-            match match st.stype {
-                StatementType::Set => match self
+            // Statements separated by `;` are executed in order, up till one fails.
+            let cmd = self.cmd.clone();
+            let statements = Statement::parse_script(&cmd);
+            let count = statements.len();
+            for (i, (text, statement)) in statements.into_iter().enumerate() {
+                let started = Instant::now();
+                self.unavailable = false;
+                self.metadata = None;
+                let mut result = self.run(text, statement).await;
+                // Statements failing for a network blip are retried once, as soon as the
+                // server can be reached again. Batched writes aren't, see exec().
+                if self.unavailable && self.reconnect().await {
+                    eprintln!("Reconnected, retrying `{}`.", text);
+                    result = self.run(text, Statement::prep(text)).await;
+                }
+                if self.verbose {
+                    println!("Time: {:?}", started.elapsed());
+                    if let Some(metadata) = &self.metadata {
+                        let source = match Source::from_i32(metadata.served_from) {
+                            Some(Source::Memtable) => "memtable",
+                            Some(Source::Cache) => "cache",
+                            Some(Source::Disk) => "disk",
+                            None => "unknown",
+                        };
+                        println!(
+                            "Server: {}µs, {} keys, {} bytes read, {} bytes written, from {}",
+                            metadata.elapsed_micros,
+                            metadata.keys,
+                            metadata.bytes_read,
+                            metadata.bytes_written,
+                            source
+                        );
+                    }
+                }
+                match result {
+                    ExecResult::Success => println!("Success: OK"),
+                    ExecResult::Failed => {
+                        eprintln!("Command Execution Failed.");
+                        if i + 1 < count {
+                            eprintln!("Skipped {} statement(s) following it.", count - i - 1);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Executes a statement, if legit, else fails printing why.
+    async fn run(&mut self, text: &str, statement: Result<Statement, ParseError>) -> ExecResult {
+        match statement {
+            Ok(st) => self.exec(st).await,
+            // Operations unknown to the parser may be custom commands registered
+            // with the server, the first word being its name and the rest args.
+            Err(ParseError {
+                kind: ParseErrorKind::UnknownCommand(_, suggestion),
+                ..
+            }) => self.exec_custom(text, suggestion).await,
+            Err(e) => {
+                eprintln!("Error: {}.", e);
+                eprintln!("{}\n{}", text, e.underline(text));
+                ExecResult::Failed
+            }
+        }
+    }
+
+    /// Executes a parsed statement on the server, printing its results. Batches written
+    /// by GENERATE and IMPORT aren't retried should the server be unreachable, as keys
+    /// written by an earlier batch would fail a retry.
+    async fn exec(&mut self, st: Statement) -> ExecResult {
+        for warning in &st.warnings {
+            eprintln!("Warning: {}.", warning);
+        }
+        let key = st.key.unwrap_or("".to_string());
+        match st.stype {
+            StatementType::Set => match st.condition {
+                None | Some(SetCondition::IfAbsent) | Some(SetCondition::IfPresent) => {
+                    let mode = match st.condition {
+                        Some(SetCondition::IfAbsent) => SetMode::IfAbsent,
+                        Some(SetCondition::IfPresent) => SetMode::IfPresent,
+                        _ => SetMode::Always,
+                    };
+                    match self
+                        .store
+                        .set(Request::new(ConditionalKeyValue {
+                            key: key.as_bytes().to_vec(),
+                            value: st.value.unwrap().as_bytes().to_vec(),
+                            id: st.id.unwrap_or_default(),
+                            mode: mode as i32,
+                        }))
+                        .await
+                    {
+                        Ok(res) => {
+                            let previous = self.served(res);
+                            match (st.previous, previous.found) {
+                                (true, true) => {
+                                    println!("{}", String::from_utf8_lossy(&previous.value))
+                                }
+                                (true, false) => println!("(nil)"),
+                                _ => {}
+                            }
+                            ExecResult::Success
+                        }
+                        Err(e) => self.failed(e),
+                    }
+                }
+                Some(SetCondition::IfVersion(version)) => match self
                     .store
-                    .set(Request::new(KeyValue {
+                    .set_if_version(Request::new(VersionedKeyValue {
                         key: key.as_bytes().to_vec(),
                         value: st.value.unwrap().as_bytes().to_vec(),
+                        version,
+                        id: st.id.unwrap_or_default(),
                     }))
                     .await
                 {
-                    Ok(_) => ExecResult::Success,
-                    Err(e) => {
-                        eprintln!("{}", e.message());
-                        ExecResult::Failed
+                    Ok(res) => {
+                        println!("Version: {}", self.served(res).version);
+                        ExecResult::Success
                     }
+                    Err(e) => self.failed(e),
                 },
-                StatementType::Get => match self
+            },
+            StatementType::Get => match st.modifier {
+                None => match self
                     .store
                     .get(Request::new(Byte {
                         body: key.as_bytes().to_vec(),
@@ -86,46 +328,505 @@ impl REPL {
                     .await
                 {
                     Ok(res) => {
-                        println!("{}", String::from_utf8(res.into_inner().body).unwrap());
+                        let res = self.served(res);
+                        println!("{}", String::from_utf8(res.body).unwrap());
+                        // Version tokens are only of use to IFVERSION writes, STAT reports
+                        // them otherwise.
+                        if self.verbose {
+                            println!("Version: {}", res.version);
+                        }
                         ExecResult::Success
                     }
-                    Err(e) => {
-                        // If the key doesn't exist, get() explicitly returns this,
-                        // so print the desired Error message.
-                        eprintln!("{}", e.message());
-                        ExecResult::Failed
+                    // If the key doesn't exist, get() explicitly returns this,
+                    // so print the desired Error message.
+                    Err(e) => self.failed(e),
+                },
+                Some(GetModifier::Range(start, len)) => match self
+                    .store
+                    .get_range(Request::new(Range {
+                        key: key.as_bytes().to_vec(),
+                        start,
+                        len,
+                    }))
+                    .await
+                {
+                    Ok(res) => {
+                        // A slice may split a multi-byte character, print it lossily.
+                        println!("{}", String::from_utf8_lossy(&self.served(res).body));
+                        ExecResult::Success
                     }
+                    Err(e) => self.failed(e),
                 },
-                StatementType::Del => match self
+                Some(GetModifier::Hash(algorithm)) => match self
                     .store
-                    .del(Request::new(Byte {
+                    .get_hash(Request::new(Hash {
+                        key: key.as_bytes().to_vec(),
+                        algorithm: algorithm.name().to_string(),
+                    }))
+                    .await
+                {
+                    Ok(res) => {
+                        let body = self.served(res).body;
+                        let digest: String = body.iter().map(|b| format!("{:02x}", b)).collect();
+                        println!("{}", digest);
+                        ExecResult::Success
+                    }
+                    Err(e) => self.failed(e),
+                },
+                Some(GetModifier::Len) => match self
+                    .store
+                    .len(Request::new(Byte {
                         body: key.as_bytes().to_vec(),
                     }))
                     .await
                 {
-                    Ok(_) => ExecResult::Success,
-                    Err(e) => {
-                        eprintln!("{}", e.message());
-                        ExecResult::Failed
+                    Ok(res) => {
+                        println!("{}", self.served(res).len);
+                        ExecResult::Success
                     }
+                    Err(e) => self.failed(e),
                 },
-                StatementType::Unk => {
-                    eprintln!("db: command not found: {}", self.cmd);
-                    ExecResult::Failed
-                }
-                StatementType::Fail => ExecResult::Failed,
-            } {
-                ExecResult::Failed => eprintln!("Command Execution Failed."),
-                ExecResult::Success => println!("Success: OK"),
+            },
+            StatementType::Del => match self
+                .store
+                .del_many(Request::new(Keys {
+                    keys: st.keys.into_iter().map(String::into_bytes).collect(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    println!("Deleted: {}", self.served(res).len);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::GetSet => match self
+                .store
+                .set(Request::new(ConditionalKeyValue {
+                    key: key.into_bytes(),
+                    value: st.value.unwrap().into_bytes(),
+                    id: "".to_string(),
+                    mode: SetMode::Always as i32,
+                }))
+                .await
+            {
+                Ok(res) => {
+                    let previous = self.served(res);
+                    match previous.found {
+                        true => println!("{}", String::from_utf8_lossy(&previous.value)),
+                        false => println!("(nil)"),
+                    }
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::GetDel => match self
+                .store
+                .get_del(Request::new(Byte {
+                    body: key.into_bytes(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    println!("{}", String::from_utf8_lossy(&self.served(res).body));
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::StrLen => match self
+                .store
+                .len(Request::new(Byte {
+                    body: key.into_bytes(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    println!("{}", self.served(res).len);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Type => match self
+                .store
+                .type_of(Request::new(Byte {
+                    body: key.into_bytes(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    println!("{}", self.served(res).name);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::MSet => match self
+                .store
+                .set_many(Request::new(KeyValues {
+                    pairs: st
+                        .pairs
+                        .into_iter()
+                        .map(|(key, value)| KeyValue {
+                            key: key.into_bytes(),
+                            value: value.into_bytes(),
+                            id: "".to_string(),
+                        })
+                        .collect(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    self.served(res);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::MGet => match self
+                .store
+                .get_many(Request::new(Keys {
+                    keys: st.keys.into_iter().map(String::into_bytes).collect(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    for lookup in self.served(res).lookups {
+                        match lookup.found {
+                            true => println!("{}", String::from_utf8_lossy(&lookup.value)),
+                            false => println!("(nil)"),
+                        }
+                    }
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Keys => match self
+                .store
+                .list_keys(Request::new(Byte {
+                    body: st.pattern.unwrap_or_default().into_bytes(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    let keys = self.served(res).keys;
+                    if keys.is_empty() {
+                        println!("(empty)");
+                    }
+                    for key in keys {
+                        println!("{}", String::from_utf8_lossy(&key));
+                    }
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Incr | StatementType::Decr | StatementType::IncrBy => match self
+                .store
+                .incr_by(Request::new(Increment {
+                    key: key.into_bytes(),
+                    delta: st.delta.unwrap(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    println!("{}", self.served(res).value);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Append => match self
+                .store
+                .append(Request::new(KeyValue {
+                    key: key.into_bytes(),
+                    value: st.value.unwrap().into_bytes(),
+                    id: "".to_string(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    println!("Length: {}", self.served(res).len);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Rename | StatementType::RenameNx => match self
+                .store
+                .rename(Request::new(Transfer {
+                    from: key.into_bytes(),
+                    to: st.target.unwrap().into_bytes(),
+                    overwrite: st.stype == StatementType::Rename,
+                }))
+                .await
+            {
+                Ok(res) => {
+                    self.served(res);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Copy => match self
+                .store
+                .copy(Request::new(Transfer {
+                    from: key.into_bytes(),
+                    to: st.target.unwrap().into_bytes(),
+                    overwrite: st.replace,
+                }))
+                .await
+            {
+                Ok(res) => {
+                    self.served(res);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Scan => {
+                let scan = st.scan.unwrap();
+                match self
+                    .store
+                    .scan_keys(Request::new(Scan {
+                        cursor: scan.cursor,
+                        pattern: scan.pattern.into_bytes(),
+                        count: scan.count,
+                    }))
+                    .await
+                {
+                    Ok(res) => {
+                        let page = self.served(res);
+                        for key in page.keys {
+                            println!("{}", String::from_utf8_lossy(&key));
+                        }
+                        println!("Cursor: {}", page.cursor);
+                        ExecResult::Success
+                    }
+                    Err(e) => self.failed(e),
+                }
+            }
+            StatementType::Stat => match self
+                .store
+                .stat(Request::new(Byte {
+                    body: key.as_bytes().to_vec(),
+                }))
+                .await
+            {
+                Ok(res) => {
+                    let res = self.served(res);
+                    println!("Created at: {} ms", res.created_at);
+                    println!("Updated at: {} ms", res.updated_at);
+                    println!("Accesses: {}", res.accesses);
+                    println!("Version: {}", res.version);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::Analyze => match self
+                .store
+                .analyze_prefixes(Request::new(Depth {
+                    depth: st.depth.unwrap() as u64,
+                }))
+                .await
+            {
+                Ok(res) => {
+                    for prefix in self.served(res).prefixes {
+                        println!(
+                            "{}\t{} keys\t{} bytes",
+                            String::from_utf8_lossy(&prefix.prefix),
+                            prefix.keys,
+                            prefix.bytes
+                        );
+                    }
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::DbHash => match self.store.db_hash(Request::new(Null {})).await {
+                Ok(res) => {
+                    println!("{:016x}", self.served(res).digest);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::DbSize => match self.store.db_size(Request::new(Null {})).await {
+                Ok(res) => {
+                    println!("{}", self.served(res).len);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            StatementType::FlushAll => match self
+                .store
+                .flush_all(Request::new(Flush {
+                    background: st.background,
+                }))
+                .await
+            {
+                Ok(res) => {
+                    println!("Deleted: {}", self.served(res).len);
+                    ExecResult::Success
+                }
+                Err(e) => self.failed(e),
+            },
+            // Described from the command table, without a round trip to the server.
+            StatementType::Help => {
+                match st.topic {
+                    Some(topic) => {
+                        let usage = topic.usage();
+                        println!("{}", usage.syntax);
+                        println!("    {}", usage.summary);
+                        println!("Keywords: {}", usage.keywords.join(", "));
+                        println!("Example: {}", usage.example);
+                    }
+                    None => {
+                        for usage in COMMANDS {
+                            println!("{:<10} {}", usage.keywords[0].to_uppercase(), usage.summary);
+                        }
+                        println!("Type `HELP <operation>` for how to write one.");
+                    }
+                }
+                ExecResult::Success
+            }
+            StatementType::Generate => {
+                // Pairs are written in batches, each with a single request.
+                let generate = st.generate.unwrap();
+                let mut written = 0;
+                let result = loop {
+                    if written == generate.count {
+                        break ExecResult::Success;
+                    }
+                    let end = generate.count.min(written + WRITE_BATCH);
+                    let pairs = (written..end)
+                        .map(|i| {
+                            let (key, value) = generate.pair(i);
+                            KeyValue {
+                                key: key.into_bytes(),
+                                value: value.into_bytes(),
+                                id: "".to_string(),
+                            }
+                        })
+                        .collect();
+                    match self.store.set_many(Request::new(KeyValues { pairs })).await {
+                        Ok(res) => {
+                            self.served(res);
+                            written = end;
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e.message());
+                            break ExecResult::Failed;
+                        }
+                    }
+                };
+                println!("Generated {} pairs.", written);
+                result
+            }
+            StatementType::Import => {
+                // Rows are read a line at a time and written in batches, each with a
+                // single request, so that files needn't fit in memory.
+                let import = st.import.unwrap();
+                let rows = std::fs::File::open(&import.path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|file| {
+                        import::Rows::new(io::BufReader::new(file), &import.key, &import.value)
+                    });
+                let mut rows = match rows {
+                    Ok(rows) => rows,
+                    Err(e) => {
+                        eprintln!("Error: Couldn't import `{}`, {}.", import.path, e);
+                        return ExecResult::Failed;
+                    }
+                };
+                let (mut imported, mut failed, mut skipped) = (0, 0, 0);
+                let mut batch = vec![];
+                let mut result = None;
+                while result.is_none() {
+                    match rows.next() {
+                        Some(Ok((key, value))) if import.dry_run => {
+                            println!("{} -> {}", key, value);
+                            imported += 1;
+                            continue;
+                        }
+                        Some(Ok((key, value))) => {
+                            batch.push(KeyValue {
+                                key: key.into_bytes(),
+                                value: value.into_bytes(),
+                                id: "".to_string(),
+                            });
+                            if (batch.len() as u64) < WRITE_BATCH {
+                                continue;
+                            }
+                        }
+                        Some(Err(e)) => {
+                            eprintln!("Error: Couldn't import `{}`, {}.", import.path, e);
+                            // Rows before the bad one are still written, the rest skipped.
+                            skipped = 1 + rows.by_ref().count();
+                            result = Some(ExecResult::Failed);
+                        }
+                        None => result = Some(ExecResult::Success),
+                    }
+                    if batch.is_empty() {
+                        continue;
+                    }
+                    let pairs = std::mem::take(&mut batch);
+                    let len = pairs.len();
+                    match self.store.set_many(Request::new(KeyValues { pairs })).await {
+                        Ok(res) => {
+                            self.served(res);
+                            imported += len;
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {} rows not imported, {}", len, e.message());
+                            failed += len;
+                        }
+                    }
+                }
+                match import.dry_run {
+                    true => println!("Would import {} rows, {} skipped.", imported, skipped),
+                    false => println!(
+                        "Imported {} rows, {} failed, {} skipped.",
+                        imported, failed, skipped
+                    ),
+                }
+                match (result, failed) {
+                    (Some(ExecResult::Success), 0) => ExecResult::Success,
+                    _ => ExecResult::Failed,
+                }
             }
         }
     }
+
+    /// Executes the statement as a custom command on the server, printing its reply.
+    /// Should there be no such command, the suggested operation is pointed out.
+    async fn exec_custom(&mut self, cmd: &str, suggestion: Option<String>) -> ExecResult {
+        let mut words = tokenize(cmd)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|word| word.text);
+        let name = words.next().unwrap_or_default();
+        match self
+            .store
+            .exec(Request::new(Command {
+                name,
+                args: words.map(String::into_bytes).collect(),
+            }))
+            .await
+        {
+            Ok(res) => {
+                println!("{}", String::from_utf8_lossy(&self.served(res).body));
+                ExecResult::Success
+            }
+            Err(e) if e.code() == Code::NotFound => {
+                eprintln!("db: command not found: {}", cmd);
+                if let Some(op) = suggestion {
+                    eprintln!("Did you mean `{}`?", op);
+                }
+                ExecResult::Failed
+            }
+            Err(e) => self.failed(e),
+        }
+    }
 }
 
 /// Used in executing meta commands on the REPL.
 pub enum MetaCmdResult {
     Success,
     Unrecognized,
+    /// Toggles printing the time taken by each statement.
+    Verbose,
 }
 
 impl MetaCmdResult {
@@ -139,7 +840,26 @@ impl MetaCmdResult {
                 }
                 Self::Success
             }
+            ".verbose" => Self::Verbose,
             _ => Self::Unrecognized,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_every_attempt() {
+        let delays: Vec<Duration> = REPL::backoff().collect();
+        assert_eq!(delays.len() as u32, CONNECT_ATTEMPTS - 1);
+        assert_eq!(
+            delays,
+            [100, 200, 400, 800, 1600]
+                .iter()
+                .map(|ms| Duration::from_millis(*ms))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,69 +1,790 @@
 use bytes::Bytes;
-use std::{net::SocketAddr, sync::Arc};
-use tokio::sync::Mutex;
-use tonic::{transport, Request, Response, Status};
+use prost::Message;
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::{Mutex, MutexGuard, Semaphore, SemaphorePermit},
+    task,
+    time::{interval, timeout},
+};
+use tonic::{metadata::MetadataValue, transport, Request, Response, Status};
 
 use crate::{
+    config::{Config, RetentionRule, ServerConfig},
     kvdb_proto::{
+        self,
         kvdb_server::{Kvdb, KvdbServer},
-        Byte, KeyValue, Null,
+        Byte, Command, ConditionalKeyValue, Depth, Digest, Flush, Hash, Hello, Increment, Integer,
+        KeyValue, KeyValues, Keys, Length, Lookup, Lookups, Null, Page, Prefix, Prefixes, Range,
+        ReplyMetadata, Scan, Source, Stat, Transfer, TypeName, Value, Version, VersionedKeyValue,
+        Welcome,
     },
-    store::{ExecResult, Store},
+    parser::{HashAlgorithm, StatementType},
+    registry::CommandRegistry,
+    snapshot,
+    store::{ExecResult, ScanError, SetMode, Store, TimedOut, MAX_SCAN_COUNT},
 };
 
+/// Latest version of the protocol spoken by the server. HELLO replies with the lower of
+/// this and the version a client announces, rejecting only version 0. No state is kept
+/// per connection, requests are handled the same whichever version was announced.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Binary metadata attached to replies of admitted requests, holding a ReplyMetadata
+/// message describing how the request was served. Clients may ignore it.
+pub const REPLY_METADATA: &str = "kvdb-reply-metadata-bin";
+
+/// Optional features that clients can ask for on HELLO.
+pub const FEATURES: &[&str] = &["versions", "write-ids", "custom-commands", "set-modes"];
+
+/// Names requests are admitted under, that ServerConfig::disabled may list besides
+/// custom commands, each along with the statements executed through them. Statements
+/// sharing a name can't be disabled apart, as they share the same RPC.
+pub const ADMISSION: &[(&str, &[&str])] = &[
+    ("get", &["GET", "GET RANGE", "GET HASH"]),
+    ("set", &["SET", "GETSET"]),
+    ("mset", &["MSET", "GENERATE", "IMPORT"]),
+    ("mget", &["MGET"]),
+    ("del", &["DEL"]),
+    ("getdel", &["GETDEL"]),
+    ("strlen", &["STRLEN", "GET LEN"]),
+    ("incr", &["INCR", "DECR", "INCRBY"]),
+    ("append", &["APPEND"]),
+    ("type", &["TYPE"]),
+    ("keys", &["KEYS"]),
+    ("scan", &["SCAN"]),
+    ("rename", &["RENAME"]),
+    ("renamenx", &["RENAMENX"]),
+    ("copy", &["COPY"]),
+    ("stat", &["STAT"]),
+    ("analyze", &["ANALYZE PREFIXES"]),
+    ("dbhash", &["DBHASH"]),
+    ("dbsize", &["DBSIZE"]),
+    ("flushall", &["FLUSHALL"]),
+];
+
+/// Work a request did on the store, reported to clients in its ReplyMetadata.
+#[derive(Default)]
+struct Usage {
+    /// Number of keys looked up, written or iterated over.
+    keys: usize,
+    /// Bytes of stored keys and values sent back to the client.
+    read: usize,
+    /// Bytes of keys and values stored.
+    written: usize,
+}
+
+impl Usage {
+    /// Usage of a request that only touched keys, without moving any data.
+    fn keys(keys: usize) -> Self {
+        Self {
+            keys,
+            ..Default::default()
+        }
+    }
+}
+
+/// A request admitted to execute, holding its permits till it replies.
+struct Admitted<'a> {
+    _permits: (SemaphorePermit<'a>, SemaphorePermit<'a>),
+    /// When the request arrived, which its elapsed time is measured from.
+    started: Instant,
+}
+
+impl Admitted<'_> {
+    /// Replies with message, attaching the ReplyMetadata of a request that did usage.
+    fn reply<T>(&self, message: T, usage: Usage) -> Response<T> {
+        let metadata = ReplyMetadata {
+            elapsed_micros: self.started.elapsed().as_micros() as u64,
+            keys: usage.keys as u64,
+            bytes_read: usage.read as u64,
+            bytes_written: usage.written as u64,
+            // The store is held in memory as a whole.
+            served_from: Source::Memtable as i32,
+        };
+        let mut buf = Vec::with_capacity(metadata.encoded_len());
+        let mut response = Response::new(message);
+        if metadata.encode(&mut buf).is_ok() {
+            response
+                .metadata_mut()
+                .insert_bin(REPLY_METADATA, MetadataValue::from_bytes(&buf));
+        }
+
+        response
+    }
+}
+
+/// Delimiter between the segments of keys, that prefixes are analyzed by.
+const KEY_DELIMITER: u8 = b':';
+
 pub struct Server {
     /// A cross thread sharable pointer to a common data-store
     store: Arc<Mutex<Store<Bytes, Bytes>>>,
+    /// Permits for requests admitted to execute, both executing and waiting.
+    admitted: Semaphore,
+    /// Permits for requests executing on the store.
+    workers: Semaphore,
+    /// Time a request may wait for a worker or the store, and iterate over keys for.
+    timeout: Duration,
+    /// Custom commands registered by the embedder.
+    registry: CommandRegistry,
+    /// Lowercase names of commands that are refused.
+    disabled: HashSet<String>,
 }
 
 impl Server {
-    /// Create and initialize a server as service with gRPC interfaces.
-    pub async fn start(addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+    /// Create and initialize a server as service with gRPC interfaces, serving store,
+    /// which may already hold data and triggers set up by the embedder. Fails if a
+    /// disabled command is neither in ADMISSION nor registered.
+    pub async fn start(
+        addr: SocketAddr,
+        config: ServerConfig,
+        store: Store<Bytes, Bytes>,
+        registry: CommandRegistry,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let disabled = Self::disabled_commands(&config.disabled, &registry)?;
+        let store = Arc::new(Mutex::new(store));
+        if !config.retention.is_empty() {
+            tokio::spawn(Self::enforce_retention(
+                store.clone(),
+                config.retention,
+                config.retention_interval,
+            ));
+        }
+        if let Some(persistence) = config.persistence {
+            tokio::spawn(Self::take_snapshots(store.clone(), persistence));
+        }
+
         transport::Server::builder()
             .add_service(KvdbServer::new(Self {
-                store: Arc::new(Mutex::new(Store::new())),
+                store,
+                admitted: Semaphore::new(config.workers + config.queue),
+                workers: Semaphore::new(config.workers),
+                timeout: config.timeout,
+                registry,
+                disabled,
             }))
             .serve(addr)
             .await?;
 
         Ok(())
     }
+
+    /// Lowercase names requests are admitted under, of the commands named, aliases of
+    /// built-in operations standing for the operation. Fails on names that aren't in
+    /// ADMISSION nor registered, which disabling would silently do nothing for.
+    fn disabled_commands(
+        names: &HashSet<String>,
+        registry: &CommandRegistry,
+    ) -> Result<HashSet<String>, String> {
+        let mut disabled = HashSet::new();
+        for name in names {
+            let name = match StatementType::check(name) {
+                Some(stype) => stype.usage().keywords[0].to_string(),
+                None => name.to_lowercase(),
+            };
+            if ADMISSION.iter().any(|(admitted, _)| *admitted == name) || registry.contains(&name) {
+                disabled.insert(name);
+                continue;
+            }
+            // Point out the name the statement is admitted under, if any.
+            let statement = ADMISSION
+                .iter()
+                .find(|(_, statements)| statements.iter().any(|s| s.eq_ignore_ascii_case(&name)));
+            return Err(match statement {
+                Some((admitted, _)) => format!(
+                    "Command `{}` can't be disabled on its own, disable `{}` instead",
+                    name, admitted
+                ),
+                None => format!("Command `{}` isn't executed by the server", name),
+            });
+        }
+
+        Ok(disabled)
+    }
+
+    /// Periodically deletes keys that are too old, as per rules.
+    async fn enforce_retention(
+        store: Arc<Mutex<Store<Bytes, Bytes>>>,
+        rules: Vec<RetentionRule>,
+        period: Duration,
+    ) {
+        let mut ticks = interval(period);
+        loop {
+            ticks.tick().await;
+            let mut store = store.lock().await;
+            for rule in &rules {
+                let expired =
+                    store.expire(|key| key.starts_with(rule.prefix.as_bytes()), rule.max_age);
+                if expired > 0 {
+                    eprintln!(
+                        "Retention: Deleted {} keys under `{}`.",
+                        expired, rule.prefix
+                    );
+                }
+            }
+        }
+    }
+
+    /// Periodically writes snapshots of the store, as per config.
+    async fn take_snapshots(store: Arc<Mutex<Store<Bytes, Bytes>>>, config: Config) {
+        let mut ticks = interval(config.snapshot_interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = snapshot::save(&store, &config).await {
+                eprintln!("Error: Couldn't write snapshot, {}", e);
+            }
+        }
+    }
+
+    /// Admits a request executing command to wait for a free worker, unless the command
+    /// is disabled or the queue is already full, in which case it is rejected as BUSY.
+    /// Permits are held till the request replies.
+    async fn admit(&self, command: &str) -> Result<Admitted<'_>, Status> {
+        let started = Instant::now();
+        if self.disabled.contains(&command.to_lowercase()) {
+            return Err(Status::permission_denied(format!(
+                "Command `{}` is disabled",
+                command
+            )));
+        }
+        let admitted = self
+            .admitted
+            .try_acquire()
+            .map_err(|_| Status::unavailable("BUSY too many requests pending"))?;
+        let worker = timeout(self.timeout, self.workers.acquire())
+            .await
+            .map_err(|_| Status::deadline_exceeded("Timeout waiting for a worker"))?;
+
+        Ok(Admitted {
+            _permits: (admitted, worker),
+            started,
+        })
+    }
+
+    /// Locks the store for use by a request, unless it takes longer than
+    /// the configured timeout, in which case the request is cancelled.
+    async fn lock(&self) -> Result<MutexGuard<'_, Store<Bytes, Bytes>>, Status> {
+        timeout(self.timeout, self.store.lock())
+            .await
+            .map_err(|_| Status::deadline_exceeded("Timeout waiting for the store"))
+    }
+
+    /// Point in time past which a request iterating over keys, having locked the store,
+    /// is cancelled, so that it can't hold the lock for longer than the configured timeout.
+    fn deadline(&self) -> Option<Instant> {
+        Some(Instant::now() + self.timeout)
+    }
+}
+
+/// Status of requests cancelled for iterating over keys past their deadline.
+fn timed_out(_: TimedOut) -> Status {
+    Status::deadline_exceeded("Timeout iterating over keys")
 }
 
 #[tonic::async_trait]
 impl Kvdb for Server {
-    /// RPC that maps KEY to VALUE, if it doesn't already exist on Server.
-    async fn set(&self, args: Request<KeyValue>) -> Result<Response<Null>, Status> {
+    /// RPC that negotiates the protocol version and optional features to be used
+    /// with a client, replying with the features out of those asked for that the
+    /// server supports.
+    async fn hello(&self, args: Request<Hello>) -> Result<Response<Welcome>, Status> {
         let args = args.into_inner();
-        let (key, value) = (args.key, args.value);
-        match self
-            .store
-            .lock()
-            .await
-            .set(Bytes::from(key), Bytes::from(value))
-        {
+        if args.protocol == 0 {
+            return Err(Status::invalid_argument(
+                "Protocol version must be at least 1",
+            ));
+        }
+        eprintln!(
+            "Hello from `{}` speaking protocol v{}",
+            args.client_name, args.protocol
+        );
+
+        Ok(Response::new(Welcome {
+            protocol: args.protocol.min(PROTOCOL_VERSION),
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            features: args
+                .features
+                .into_iter()
+                .filter(|feature| FEATURES.contains(&feature.as_str()))
+                .collect(),
+        }))
+    }
+
+    /// RPC that does nothing, used by clients to keep connections alive.
+    async fn ping(&self, _: Request<Null>) -> Result<Response<Null>, Status> {
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC that maps KEY to VALUE as MODE allows, by default only if it doesn't already
+    /// exist on Server, returning the VALUE replaced, if any. Retries of a write carrying
+    /// the same ID succeed without writing again, returning no VALUE.
+    async fn set(&self, args: Request<ConditionalKeyValue>) -> Result<Response<Lookup>, Status> {
+        let admitted = self.admit("set").await?;
+        let args = args.into_inner();
+        let mode = match kvdb_proto::SetMode::from_i32(args.mode) {
+            Some(kvdb_proto::SetMode::IfAbsent) => SetMode::IfAbsent,
+            Some(kvdb_proto::SetMode::Always) => SetMode::Always,
+            Some(kvdb_proto::SetMode::IfPresent) => SetMode::IfPresent,
+            None => return Err(Status::invalid_argument("Unknown set mode")),
+        };
+        let (key, value) = (Bytes::from(args.key), Bytes::from(args.value));
+        let written = key.len() + value.len();
+        let mut store = self.lock().await?;
+        let result = match args.id.is_empty() {
+            true => store.set(key, value, mode),
+            false => {
+                // Only version tokens are remembered, so retries can't return the value replaced.
+                let mut previous = None;
+                store
+                    .apply_once(args.id, |store| {
+                        previous = store.set(key.clone(), value, mode)?;
+                        store.stat(key).map(|meta| meta.version)
+                    })
+                    .map(|_| previous)
+            }
+        };
+        match result {
+            Ok(previous) => {
+                let value = previous.as_ref().map_or(vec![], |value| value.to_vec());
+                let usage = Usage {
+                    keys: 1,
+                    read: value.len(),
+                    written,
+                };
+                let found = previous.is_some();
+                Ok(admitted.reply(Lookup { found, value }, usage))
+            }
+            Err(_) if mode == SetMode::IfPresent => Err(Status::not_found("Key not in use")),
+            Err(_) => Err(Status::already_exists("Key in use")),
+        }
+    }
+
+    /// RPC that maps every KEY to its VALUE at once, if none of them already exist on Server.
+    async fn set_many(&self, args: Request<KeyValues>) -> Result<Response<Null>, Status> {
+        let admitted = self.admit("mset").await?;
+        let pairs: Vec<(Bytes, Bytes)> = args
+            .into_inner()
+            .pairs
+            .into_iter()
+            .map(|pair| (Bytes::from(pair.key), Bytes::from(pair.value)))
+            .collect();
+        let usage = Usage {
+            keys: pairs.len(),
+            written: pairs
+                .iter()
+                .map(|(key, value)| key.len() + value.len())
+                .sum(),
+            ..Default::default()
+        };
+        match self.lock().await?.set_many(pairs) {
             ExecResult::Failed => Err(Status::already_exists("Key in use")),
-            ExecResult::Success => Ok(Response::new(Null {})),
+            ExecResult::Success => Ok(admitted.reply(Null {}, usage)),
         }
     }
 
-    /// RPC that returns VALUE mapped to KEY, erring if it doesn't exist.
-    async fn get(&self, args: Request<Byte>) -> Result<Response<Byte>, Status> {
+    /// RPC that returns VALUE mapped to KEY along with its version token, erring if it doesn't exist.
+    async fn get(&self, args: Request<Byte>) -> Result<Response<Value>, Status> {
+        let admitted = self.admit("get").await?;
         let key = args.into_inner().body;
-        match self.store.lock().await.get(Bytes::from(key)) {
+        match self.lock().await?.get_versioned(Bytes::from(key)) {
             Err(_) => Err(Status::not_found("Key not in use")),
-            Ok(value) => Ok(Response::new(Byte {
-                body: value.to_vec(),
-            })),
+            Ok((value, version)) => {
+                let usage = Usage {
+                    keys: 1,
+                    read: value.len(),
+                    ..Default::default()
+                };
+                let body = value.to_vec();
+                Ok(admitted.reply(Value { body, version }, usage))
+            }
         }
     }
 
+    /// RPC that returns VALUE mapped to each of KEYS, in order, marking those
+    /// that don't exist as not found.
+    async fn get_many(&self, args: Request<Keys>) -> Result<Response<Lookups>, Status> {
+        let admitted = self.admit("mget").await?;
+        let keys = args
+            .into_inner()
+            .keys
+            .into_iter()
+            .map(Bytes::from)
+            .collect();
+        let lookups: Vec<Lookup> = self
+            .lock()
+            .await?
+            .get_many(keys)
+            .into_iter()
+            .map(|value| Lookup {
+                found: value.is_some(),
+                value: value.map_or(vec![], |value| value.to_vec()),
+            })
+            .collect();
+        let usage = Usage {
+            keys: lookups.len(),
+            read: lookups.iter().map(|lookup| lookup.value.len()).sum(),
+            ..Default::default()
+        };
+
+        Ok(admitted.reply(Lookups { lookups }, usage))
+    }
+
     /// RPC that removes a KEY -> VALUE mapping, erring if it doesn't exist.
     async fn del(&self, args: Request<Byte>) -> Result<Response<Null>, Status> {
+        let admitted = self.admit("del").await?;
         let key = args.into_inner().body;
-        match self.store.lock().await.del(Bytes::from(key)) {
+        match self.lock().await?.del(Bytes::from(key)) {
             ExecResult::Failed => Err(Status::not_found("Key not in use")),
-            ExecResult::Success => Ok(Response::new(Null {})),
+            ExecResult::Success => Ok(admitted.reply(Null {}, Usage::keys(1))),
+        }
+    }
+
+    /// RPC that removes the KEY -> VALUE mapping, returning VALUE, erring if it doesn't exist.
+    async fn get_del(&self, args: Request<Byte>) -> Result<Response<Byte>, Status> {
+        let admitted = self.admit("getdel").await?;
+        let key = args.into_inner().body;
+        match self.lock().await?.take(Bytes::from(key)) {
+            Err(_) => Err(Status::not_found("Key not in use")),
+            Ok(value) => {
+                let usage = Usage {
+                    keys: 1,
+                    read: value.len(),
+                    ..Default::default()
+                };
+                let body = value.to_vec();
+                Ok(admitted.reply(Byte { body }, usage))
+            }
         }
     }
+
+    /// RPC that removes the KEY -> VALUE mappings of all KEYS that exist, returning their number.
+    async fn del_many(&self, args: Request<Keys>) -> Result<Response<Length>, Status> {
+        let admitted = self.admit("del").await?;
+        let keys: Vec<Bytes> = args
+            .into_inner()
+            .keys
+            .into_iter()
+            .map(Bytes::from)
+            .collect();
+        let usage = Usage::keys(keys.len());
+        let len = self.lock().await?.del_many(keys) as u64;
+
+        Ok(admitted.reply(Length { len }, usage))
+    }
+
+    /// RPC that adds DELTA to the integer VALUE mapped to KEY, 0 if it doesn't exist,
+    /// returning the result, erring if VALUE isn't an integer or the result overflows.
+    async fn incr_by(&self, args: Request<Increment>) -> Result<Response<Integer>, Status> {
+        let admitted = self.admit("incr").await?;
+        let args = args.into_inner();
+        match self
+            .lock()
+            .await?
+            .incr_by(Bytes::from(args.key), args.delta)
+        {
+            Err(_) => Err(Status::failed_precondition(
+                "Value is not an integer or out of range",
+            )),
+            Ok(value) => {
+                let usage = Usage {
+                    keys: 1,
+                    written: value.to_string().len(),
+                    ..Default::default()
+                };
+                Ok(admitted.reply(Integer { value }, usage))
+            }
+        }
+    }
+
+    /// RPC that appends VALUE to the VALUE mapped to KEY, mapping KEY to it if it
+    /// doesn't exist, returning the length of the result.
+    async fn append(&self, args: Request<KeyValue>) -> Result<Response<Length>, Status> {
+        let admitted = self.admit("append").await?;
+        let args = args.into_inner();
+        let len = self
+            .lock()
+            .await?
+            .append(Bytes::from(args.key), &args.value) as u64;
+        let usage = Usage {
+            keys: 1,
+            written: args.value.len(),
+            ..Default::default()
+        };
+
+        Ok(admitted.reply(Length { len }, usage))
+    }
+
+    /// RPC that returns the name of the type of VALUE mapped to KEY, `none` if it doesn't exist.
+    async fn type_of(&self, args: Request<Byte>) -> Result<Response<TypeName>, Status> {
+        let admitted = self.admit("type").await?;
+        let key = args.into_inner().body;
+        let name = self
+            .lock()
+            .await?
+            .type_of(Bytes::from(key))
+            .map_or("none", |value_type| value_type.name())
+            .to_string();
+
+        Ok(admitted.reply(TypeName { name }, Usage::keys(1)))
+    }
+
+    /// RPC that returns all KEYS matching a glob pattern, in bytewise order.
+    async fn list_keys(&self, args: Request<Byte>) -> Result<Response<Keys>, Status> {
+        let admitted = self.admit("keys").await?;
+        let pattern = args.into_inner().body;
+        let store = self.lock().await?;
+        let keys: Vec<Vec<u8>> = store
+            .keys(&pattern, self.deadline())
+            .map_err(timed_out)?
+            .into_iter()
+            .map(|key| key.to_vec())
+            .collect();
+        let usage = Usage {
+            keys: store.len(),
+            read: keys.iter().map(Vec::len).sum(),
+            ..Default::default()
+        };
+
+        Ok(admitted.reply(Keys { keys }, usage))
+    }
+
+    /// RPC that returns a page of up to COUNT KEYS matching a glob pattern that follow
+    /// a cursor, along with the cursor to continue from, `0` once all were returned.
+    async fn scan_keys(&self, args: Request<Scan>) -> Result<Response<Page>, Status> {
+        let admitted = self.admit("scan").await?;
+        let args = args.into_inner();
+        let store = self.lock().await?;
+        match store.scan(
+            &args.cursor,
+            &args.pattern,
+            args.count.min(MAX_SCAN_COUNT as u64) as usize,
+            self.deadline(),
+        ) {
+            Err(ScanError::InvalidCursor) => Err(Status::invalid_argument("Invalid cursor")),
+            Err(ScanError::TimedOut) => Err(timed_out(TimedOut)),
+            Ok((keys, cursor)) => {
+                let keys: Vec<Vec<u8>> = keys.into_iter().map(|key| key.to_vec()).collect();
+                let usage = Usage {
+                    keys: store.len(),
+                    read: keys.iter().map(Vec::len).sum(),
+                    ..Default::default()
+                };
+                Ok(admitted.reply(Page { keys, cursor }, usage))
+            }
+        }
+    }
+
+    /// RPC that moves the VALUE mapped to FROM to the KEY TO, replacing any VALUE it is
+    /// mapped to only if OVERWRITE is set, erring first if FROM doesn't exist. Moving a KEY
+    /// onto itself changes nothing, and errs as TO being in use unless OVERWRITE is set.
+    async fn rename(&self, args: Request<Transfer>) -> Result<Response<Null>, Status> {
+        let args = args.into_inner();
+        let admitted = self
+            .admit(if args.overwrite { "rename" } else { "renamenx" })
+            .await?;
+        let (from, to) = (Bytes::from(args.from), Bytes::from(args.to));
+        let mut store = self.lock().await?;
+        match store.rename(from.clone(), to, args.overwrite) {
+            ExecResult::Success => Ok(admitted.reply(Null {}, Usage::keys(2))),
+            ExecResult::Failed if store.stat(from).is_err() => {
+                Err(Status::not_found("Key not in use"))
+            }
+            ExecResult::Failed => Err(Status::already_exists("Key in use")),
+        }
+    }
+
+    /// RPC that maps the KEY TO to a copy of the VALUE mapped to FROM, replacing any VALUE
+    /// it is mapped to only if OVERWRITE is set, erring first if FROM doesn't exist. Copying
+    /// a KEY onto itself behaves as with rename.
+    async fn copy(&self, args: Request<Transfer>) -> Result<Response<Null>, Status> {
+        let admitted = self.admit("copy").await?;
+        let args = args.into_inner();
+        let (from, to) = (Bytes::from(args.from), Bytes::from(args.to));
+        let mut store = self.lock().await?;
+        match store.copy(from.clone(), to, args.overwrite) {
+            ExecResult::Success => Ok(admitted.reply(Null {}, Usage::keys(2))),
+            ExecResult::Failed if store.stat(from).is_err() => {
+                Err(Status::not_found("Key not in use"))
+            }
+            ExecResult::Failed => Err(Status::already_exists("Key in use")),
+        }
+    }
+
+    /// RPC that returns a slice of the VALUE mapped to KEY, clamped to the
+    /// bounds of VALUE, erring if it doesn't exist.
+    async fn get_range(&self, args: Request<Range>) -> Result<Response<Byte>, Status> {
+        let admitted = self.admit("get").await?;
+        let args = args.into_inner();
+        match self.lock().await?.get(Bytes::from(args.key)) {
+            Err(_) => Err(Status::not_found("Key not in use")),
+            Ok(value) => {
+                let start = (args.start as usize).min(value.len());
+                let end = start.saturating_add(args.len as usize).min(value.len());
+                let usage = Usage {
+                    keys: 1,
+                    read: end - start,
+                    ..Default::default()
+                };
+                let body = value.slice(start..end).to_vec();
+                Ok(admitted.reply(Byte { body }, usage))
+            }
+        }
+    }
+
+    /// RPC that returns the digest of VALUE mapped to KEY by ALGORITHM, as big-endian bytes,
+    /// erring if it doesn't exist or ALGORITHM isn't supported, see parser::HashAlgorithm.
+    async fn get_hash(&self, args: Request<Hash>) -> Result<Response<Byte>, Status> {
+        let admitted = self.admit("get").await?;
+        let args = args.into_inner();
+        let algorithm = HashAlgorithm::from_name(&args.algorithm)
+            .ok_or_else(|| Status::invalid_argument("Unsupported hash algorithm"))?;
+        let store = self.lock().await?;
+        let digest = match algorithm {
+            HashAlgorithm::Fnv1a64 => store.value_fnv1a(Bytes::from(args.key)),
+        };
+        match digest {
+            Err(_) => Err(Status::not_found("Key not in use")),
+            Ok(digest) => {
+                let body = digest.to_be_bytes().to_vec();
+                Ok(admitted.reply(Byte { body }, Usage::keys(1)))
+            }
+        }
+    }
+
+    /// RPC that returns the length in bytes of VALUE mapped to KEY, erring if it doesn't exist.
+    async fn len(&self, args: Request<Byte>) -> Result<Response<Length>, Status> {
+        let admitted = self.admit("strlen").await?;
+        let key = args.into_inner().body;
+        match self.lock().await?.value_len(Bytes::from(key)) {
+            Err(_) => Err(Status::not_found("Key not in use")),
+            Ok(len) => {
+                let len = len as u64;
+                Ok(admitted.reply(Length { len }, Usage::keys(1)))
+            }
+        }
+    }
+
+    /// RPC that replaces VALUE mapped to KEY if its version token is still VERSION,
+    /// returning the new version token. Retries of a write carrying the same ID
+    /// succeed without writing again, returning the same version token.
+    async fn set_if_version(
+        &self,
+        args: Request<VersionedKeyValue>,
+    ) -> Result<Response<Version>, Status> {
+        let admitted = self.admit("set").await?;
+        let args = args.into_inner();
+        let (key, value, version) = (Bytes::from(args.key), Bytes::from(args.value), args.version);
+        let usage = Usage {
+            keys: 1,
+            written: key.len() + value.len(),
+            ..Default::default()
+        };
+        let mut store = self.lock().await?;
+        let result = match args.id.is_empty() {
+            true => store.set_if_version(key, value, version),
+            false => store.apply_once(args.id, |store| store.set_if_version(key, value, version)),
+        };
+        match result {
+            Err(_) => Err(Status::failed_precondition("Version mismatch")),
+            Ok(version) => Ok(admitted.reply(Version { version }, usage)),
+        }
+    }
+
+    /// RPC that executes a custom command registered with the server.
+    async fn exec(&self, args: Request<Command>) -> Result<Response<Byte>, Status> {
+        let args = args.into_inner();
+        let admitted = self.admit(&args.name).await?;
+        let cmd_args: Vec<Bytes> = args.args.into_iter().map(Bytes::from).collect();
+        let body = self
+            .registry
+            .exec(&mut *self.lock().await?, &args.name, &cmd_args)?;
+
+        // What custom commands do with the store isn't known.
+        let body = body.to_vec();
+        Ok(admitted.reply(Byte { body }, Usage::default()))
+    }
+
+    /// RPC that returns metadata recorded for KEY, erring if it doesn't exist.
+    /// Timestamps are in milliseconds since the UNIX epoch.
+    async fn stat(&self, args: Request<Byte>) -> Result<Response<Stat>, Status> {
+        let admitted = self.admit("stat").await?;
+        let key = args.into_inner().body;
+        let millis = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64)
+        };
+        match self.lock().await?.stat(Bytes::from(key)) {
+            Err(_) => Err(Status::not_found("Key not in use")),
+            Ok(meta) => {
+                let stat = Stat {
+                    created_at: millis(meta.created_at),
+                    updated_at: millis(meta.updated_at),
+                    accesses: meta.accesses,
+                    version: meta.version,
+                };
+                Ok(admitted.reply(stat, Usage::keys(1)))
+            }
+        }
+    }
+
+    /// RPC that returns the number and size of keys grouped by their prefixes,
+    /// up to and including the DEPTH-th `:`.
+    async fn analyze_prefixes(&self, args: Request<Depth>) -> Result<Response<Prefixes>, Status> {
+        let admitted = self.admit("analyze").await?;
+        let depth = args.into_inner().depth as usize;
+        let store = self.lock().await?;
+        let prefixes = store
+            .analyze_prefixes(KEY_DELIMITER, depth, self.deadline())
+            .map_err(timed_out)?
+            .into_iter()
+            .map(|(prefix, stats)| Prefix {
+                prefix,
+                keys: stats.keys,
+                bytes: stats.bytes,
+            })
+            .collect();
+        let usage = Usage::keys(store.len());
+
+        Ok(admitted.reply(Prefixes { prefixes }, usage))
+    }
+
+    /// RPC that returns a digest of all KEY -> VALUE mappings, equal only for
+    /// servers holding exactly the same data.
+    async fn db_hash(&self, _: Request<Null>) -> Result<Response<Digest>, Status> {
+        let admitted = self.admit("dbhash").await?;
+        let store = self.lock().await?;
+        let digest = store.digest(self.deadline()).map_err(timed_out)?;
+        let usage = Usage::keys(store.len());
+
+        Ok(admitted.reply(Digest { digest }, usage))
+    }
+
+    /// RPC that removes all KEY -> VALUE mappings on Server, returning their number. With
+    /// BACKGROUND set, memory is freed on another thread, rather than before responding.
+    async fn flush_all(&self, args: Request<Flush>) -> Result<Response<Length>, Status> {
+        let admitted = self.admit("flushall").await?;
+        let cleared = self.lock().await?.clear();
+        let len = cleared.len() as u64;
+        let usage = Usage::keys(cleared.len());
+        match args.into_inner().background {
+            true => {
+                task::spawn_blocking(move || drop(cleared));
+            }
+            false => drop(cleared),
+        }
+
+        Ok(admitted.reply(Length { len }, usage))
+    }
+
+    /// RPC that returns the number of KEYS on Server.
+    async fn db_size(&self, _: Request<Null>) -> Result<Response<Length>, Status> {
+        let admitted = self.admit("dbsize").await?;
+        let len = self.lock().await?.len() as u64;
+
+        Ok(admitted.reply(Length { len }, Usage::default()))
+    }
 }
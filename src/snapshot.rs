@@ -0,0 +1,307 @@
+use bytes::Bytes;
+use std::{
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex, task};
+
+use crate::{config::Config, object_store::ObjectStore, store::Store};
+
+/// Name of the file within the data directory holding the latest snapshot.
+const SNAPSHOT: &str = "snapshot";
+/// Name of the file holding the generation of snapshot before the latest.
+const PREVIOUS: &str = "snapshot.prev";
+/// Name of the file a new snapshot is written to, before becoming the latest.
+const PENDING: &str = "snapshot.tmp";
+/// Prefix of the names snapshots are archived under, followed by when they were taken.
+const ARCHIVED: &str = "snapshot-";
+
+/// Loads the latest snapshot from the data directory at path, falling back to the
+/// previous generation if the latest is missing or unreadable, e.g. after a crash
+/// while it was being written. Returns an empty store if neither exists. A pending
+/// snapshot that was completely written before a crash is loaded instead.
+pub fn load(path: &str) -> io::Result<Store<Bytes, Bytes>> {
+    if let Some(store) = recover_pending(Path::new(path))? {
+        return Ok(store);
+    }
+    let latest = match load_file(&Path::new(path).join(SNAPSHOT)) {
+        Ok(store) => return Ok(store),
+        Err(e) => e,
+    };
+    match load_file(&Path::new(path).join(PREVIOUS)) {
+        Ok(store) => {
+            if latest.kind() != io::ErrorKind::NotFound {
+                eprintln!(
+                    "Warning: Latest snapshot unreadable, {}, loaded previous snapshot.",
+                    latest
+                );
+            }
+            Ok(store)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => match latest.kind() {
+            io::ErrorKind::NotFound => Ok(Store::new()),
+            _ => Err(latest),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Handles a pending snapshot left in the data directory at dir by a crash. Should it
+/// have been completely written, i.e. the crash came before or between the renames
+/// making it the latest, the renames are finished and the snapshot returned, as it is
+/// the newest. As the crash may have come before it was synced to disk, it only counts
+/// as completely written if its checksum matches, see Store::load(). Otherwise it is
+/// deleted, keeping the latest snapshot, as it would only take up space till the next
+/// snapshot. Other files aren't touched, as the directory may be shared with others.
+fn recover_pending(dir: &Path) -> io::Result<Option<Store<Bytes, Bytes>>> {
+    let pending = dir.join(PENDING);
+    let len = match std::fs::metadata(&pending) {
+        Ok(meta) => meta.len(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    match load_file(&pending) {
+        Ok(store) => {
+            // Only what was read from the page cache is verified, so it is synced first.
+            std::fs::File::open(&pending)?.sync_all()?;
+            match std::fs::rename(dir.join(SNAPSHOT), dir.join(PREVIOUS)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            std::fs::rename(&pending, dir.join(SNAPSHOT))?;
+            std::fs::File::open(dir)?.sync_all()?;
+            eprintln!(
+                "Recovered completely written snapshot `{}`.",
+                pending.display()
+            );
+            Ok(Some(store))
+        }
+        Err(e)
+            if e.kind() == io::ErrorKind::InvalidData
+                || e.kind() == io::ErrorKind::UnexpectedEof =>
+        {
+            std::fs::remove_file(&pending)?;
+            eprintln!(
+                "Removed unfinished snapshot `{}`, reclaiming {} bytes.",
+                pending.display(),
+                len
+            );
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn load_file(path: &Path) -> io::Result<Store<Bytes, Bytes>> {
+    Store::load(io::BufReader::new(std::fs::File::open(path)?))
+}
+
+/// Loads a snapshot archived as name in objects.
+pub fn load_archived(objects: &dyn ObjectStore, name: &str) -> io::Result<Store<Bytes, Bytes>> {
+    Store::load(&objects.get(name)?[..])
+}
+
+/// Writes a snapshot of store into the data directory in config, also archiving it
+/// if configured to, as `snapshot-<milliseconds since UNIX epoch>`, and pruning older
+/// archives past the configured retention. The latest snapshot is kept as the previous
+/// generation, and only replaced once the new one has been completely written and
+/// synced to disk.
+pub async fn save(store: &Mutex<Store<Bytes, Bytes>>, config: &Config) -> io::Result<()> {
+    // Serialize in memory, so that the store isn't locked during disk IO.
+    let mut buf = vec![];
+    store.lock().await.save(&mut buf)?;
+
+    write(&buf, &config.path).await?;
+    if let Some(archive) = &config.archive {
+        let (archive, retention) = (archive.clone(), config.archive_retention);
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        // Object stores may well block on network IO.
+        task::spawn_blocking(move || {
+            archive.put(&format!("{}{}", ARCHIVED, millis), &buf)?;
+            match retention {
+                Some(keep) => prune_archives(&*archive, keep),
+                None => Ok(()),
+            }
+        })
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))??;
+    }
+
+    Ok(())
+}
+
+/// Deletes all but the keep latest snapshots archived in objects, at least one being
+/// kept. Objects not named as archived snapshots are left alone.
+fn prune_archives(objects: &dyn ObjectStore, keep: usize) -> io::Result<()> {
+    let mut archived: Vec<(u128, String)> = objects
+        .list()?
+        .into_iter()
+        .filter_map(|name| Some((name.strip_prefix(ARCHIVED)?.parse().ok()?, name)))
+        .collect();
+    archived.sort_unstable();
+    let stale = archived.len().saturating_sub(keep.max(1));
+    for (_, name) in archived.drain(..stale) {
+        objects.delete(&name)?;
+    }
+
+    Ok(())
+}
+
+/// Writes snapshot data into the data directory at path.
+async fn write(buf: &[u8], path: &str) -> io::Result<()> {
+    let dir = Path::new(path);
+    fs::create_dir_all(dir).await?;
+    let mut file = fs::File::create(dir.join(PENDING)).await?;
+    file.write_all(buf).await?;
+    file.sync_all().await?;
+
+    match fs::rename(dir.join(SNAPSHOT), dir.join(PREVIOUS)).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    fs::rename(dir.join(PENDING), dir.join(SNAPSHOT)).await?;
+    // Renames are only durable once the directory itself is synced.
+    fs::File::open(dir).await?.sync_all().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{object_store::FsObjectStore, store::SetMode};
+    use std::path::PathBuf;
+
+    /// An empty data directory, unique to the test named name.
+    fn data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kvdb-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a snapshot of a store holding only key, with value, to path.
+    fn write_snapshot(path: &Path, key: &str, value: &str) {
+        let mut store = Store::new();
+        store
+            .set(
+                Bytes::from(key.to_string()),
+                Bytes::from(value.to_string()),
+                SetMode::Always,
+            )
+            .unwrap();
+        let mut buf = vec![];
+        store.save(&mut buf).unwrap();
+        std::fs::write(path, buf).unwrap();
+    }
+
+    fn value_of(store: &Store<Bytes, Bytes>, key: &str) -> Option<Bytes> {
+        store.get(Bytes::from(key.to_string())).ok()
+    }
+
+    #[test]
+    fn test_load_falls_back_on_corrupt_snapshot() {
+        let dir = data_dir("corrupt-snapshot");
+        write_snapshot(&dir.join(PREVIOUS), "k", "previous");
+        std::fs::write(dir.join(SNAPSHOT), b"KVDB garbage").unwrap();
+
+        let store = load(dir.to_str().unwrap()).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("previous")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_falls_back_on_missing_snapshot() {
+        let dir = data_dir("missing-snapshot");
+        write_snapshot(&dir.join(PREVIOUS), "k", "previous");
+
+        let store = load(dir.to_str().unwrap()).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("previous")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_pruning_archives() {
+        let dir = data_dir("archives");
+        let objects = FsObjectStore::new(&dir).unwrap();
+        for name in &["snapshot-20", "snapshot-100", "snapshot-3", "notes"] {
+            objects.put(name, b"data").unwrap();
+        }
+
+        prune_archives(&objects, 2).unwrap();
+        let mut names = objects.list().unwrap();
+        names.sort();
+        assert_eq!(names, vec!["notes", "snapshot-100", "snapshot-20"]);
+        prune_archives(&objects, 0).unwrap();
+        let mut names = objects.list().unwrap();
+        names.sort();
+        assert_eq!(names, vec!["notes", "snapshot-100"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_removes_unfinished_snapshot() {
+        let dir = data_dir("unfinished-snapshot");
+        write_snapshot(&dir.join(SNAPSHOT), "k", "latest");
+        std::fs::write(dir.join(PENDING), b"KVDB").unwrap();
+
+        let store = load(dir.to_str().unwrap()).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("latest")));
+        assert!(!dir.join(PENDING).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_removes_corrupted_pending_snapshot() {
+        let dir = data_dir("corrupted-pending-snapshot");
+        write_snapshot(&dir.join(SNAPSHOT), "k", "latest");
+        write_snapshot(&dir.join(PENDING), "k", "pending");
+        // Corrupts the value, which leaves the pending snapshot otherwise well-formed.
+        let mut buf = std::fs::read(dir.join(PENDING)).unwrap();
+        let at = buf.len() - 9;
+        buf[at] ^= 1;
+        std::fs::write(dir.join(PENDING), buf).unwrap();
+
+        let store = load(dir.to_str().unwrap()).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("latest")));
+        assert!(!dir.join(PENDING).exists());
+        assert!(!dir.join(PREVIOUS).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_recovers_snapshot_written_before_crash() {
+        // Crashed between the renames, the latest snapshot already moved aside.
+        let dir = data_dir("crash-between-renames");
+        write_snapshot(&dir.join(PREVIOUS), "k", "previous");
+        write_snapshot(&dir.join(PENDING), "k", "pending");
+
+        let store = load(dir.to_str().unwrap()).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("pending")));
+        assert!(!dir.join(PENDING).exists());
+        let store = load_file(&dir.join(PREVIOUS)).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("previous")));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Crashed before the renames, the latest snapshot still in place.
+        let dir = data_dir("crash-before-renames");
+        write_snapshot(&dir.join(SNAPSHOT), "k", "latest");
+        write_snapshot(&dir.join(PENDING), "k", "pending");
+
+        let store = load(dir.to_str().unwrap()).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("pending")));
+        let store = load_file(&dir.join(PREVIOUS)).unwrap();
+        assert_eq!(value_of(&store, "k"), Some(Bytes::from("latest")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_without_snapshots() {
+        let dir = data_dir("no-snapshots");
+
+        let store = load(dir.to_str().unwrap()).unwrap();
+        assert!(store.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,11 @@
 use crate::config::Config;
 use crate::lsmt::{LSMTError, LSMT};
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::convert::TryInto;
 use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Depicts whether an operation was successfully executed or not.
 #[cfg_attr(test, derive(PartialEq, Debug))]
@@ -10,32 +14,328 @@ pub enum ExecResult {
     Failed,
 }
 
+/// Returned by iterations over all keys that were still running past their deadline.
+#[derive(Debug, PartialEq)]
+pub struct TimedOut;
+
+/// Reasons scan() fails for.
+#[derive(Debug, PartialEq)]
+pub enum ScanError {
+    /// The cursor wasn't returned by scan().
+    InvalidCursor,
+    /// The deadline passed before the page was complete.
+    TimedOut,
+}
+
+impl From<TimedOut> for ScanError {
+    fn from(_: TimedOut) -> Self {
+        Self::TimedOut
+    }
+}
+
+/// A value as held by the Storage Engine, along with its version token and metadata.
+struct Entry<B> {
+    value: B,
+    /// Changes every time the value associated with a key is written.
+    version: u64,
+    /// When the key was first associated with a value.
+    created_at: SystemTime,
+    /// When the key was last written to.
+    updated_at: SystemTime,
+    /// Number of times the value was read, counted through a shared reference.
+    accesses: Cell<u64>,
+}
+
+/// Metadata recorded by the Storage Engine for each key.
+#[cfg_attr(test, derive(PartialEq, Debug))]
+pub struct Metadata {
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+    /// An approximate count of reads of the key.
+    pub accesses: u64,
+    /// The key's current version token.
+    pub version: u64,
+}
+
+/// When set() is allowed to write, given whether the key is associated with a value.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum SetMode {
+    /// Always, replacing any value associated with the key.
+    Always,
+    /// Only if the key isn't associated with a value.
+    IfAbsent,
+    /// Only if the key is already associated with a value, which is replaced.
+    IfPresent,
+}
+
+/// Types of values held by the Storage Engine, as told apart by their contents.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum ValueType {
+    /// An integer written in decimal, which incr_by() can adjust.
+    Int,
+    /// Any other string of bytes.
+    String,
+}
+
+impl ValueType {
+    /// Name of the type as shown to users.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Int => "int",
+            Self::String => "string",
+        }
+    }
+}
+
+/// A write made to the Storage Engine, as seen by triggers.
+pub enum Event<'a, A, B> {
+    /// Key was associated with value.
+    Set(&'a A, &'a B),
+    /// Key was removed along with its value.
+    Del(&'a A),
+}
+
+impl<'a, A, B> Event<'a, A, B> {
+    /// The key written to.
+    pub fn key(&self) -> &'a A {
+        match self {
+            Self::Set(key, _) | Self::Del(key) => key,
+        }
+    }
+}
+
+// Events only hold references, so are copyable whatever A and B are.
+impl<'a, A, B> Clone for Event<'a, A, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, A, B> Copy for Event<'a, A, B> {}
+
+/// Key-value pairs removed from the Storage Engine all at once, which are only freed
+/// once this is dropped, so that large stores can be freed e.g. on another thread.
+pub struct Cleared<A, B>(HashMap<A, Entry<B>>);
+
+impl<A, B> Cleared<A, B> {
+    /// Number of keys removed.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no keys were removed.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Handler run by a trigger, with the store and the write that triggered it.
+type TriggerFn<A, B> = Box<dyn Fn(&mut Store<A, B>, Event<A, B>) + Send>;
+
+/// Runs handler on writes to keys that matches accepts.
+struct Trigger<A, B> {
+    matches: Box<dyn Fn(&A) -> bool + Send>,
+    handler: TriggerFn<A, B>,
+}
+
 /// The Storage Engine
 pub struct Store<A, B> {
     /// A KV store in the form of in-memory HashMap.
     /// Types A and B can be defined by the use case.
-    storage: HashMap<A, B>,
+    storage: HashMap<A, Entry<B>>,
+    /// Version token handed out to the latest write, tokens are never
+    /// reused so that a deleted and recreated key doesn't match old tokens.
+    version: u64,
+    /// Handlers to be run on writes, in order of registration.
+    triggers: Vec<Trigger<A, B>>,
+    /// IDs of the latest writes applied through apply_once(), mapped
+    /// to the version tokens produced, along with their order.
+    applied: HashMap<String, u64>,
+    applied_order: VecDeque<String>,
+}
+
+/// Number of write IDs remembered, older IDs are forgotten first.
+const WRITE_ID_WINDOW: usize = 10_000;
+
+/// Most keys returned in a page by scan(), bounding the memory a page takes up.
+pub const MAX_SCAN_COUNT: usize = 10_000;
+
+/// Number of keys iterated over between checks of the deadline, keeping checks cheap.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+/// Fails if deadline has passed, only checked on every DEADLINE_CHECK_INTERVAL-th key
+/// i iterated over, starting with the first. Iterations without a deadline never fail.
+fn check_deadline(deadline: Option<Instant>, i: usize) -> Result<(), TimedOut> {
+    match deadline {
+        Some(deadline)
+            if i.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline =>
+        {
+            Err(TimedOut)
+        }
+        _ => Ok(()),
+    }
 }
 
 /// As is clear from the implementation, types A and B must implement Display
-/// to be 'printable'. While A must also implement Hash and Eq traits
-impl<A: Hash + Eq, B: Clone> Store<A, B> {
+/// to be 'printable'. While A must also implement Hash, Eq and Clone traits
+impl<A: Hash + Eq + Clone, B: Clone> Store<A, B> {
     /// Creates a new Storage Engine.
     pub fn new() -> Self {
         Self {
             storage: HashMap::new(),
+            version: 0,
+            triggers: vec![],
+            applied: HashMap::new(),
+            applied_order: VecDeque::new(),
         }
     }
 
-    /// Operates HashMap::insert()
-    pub fn set(&mut self, key: A, value: B) -> ExecResult {
-        // Fails if key already points to another value, else stores key-value pair and returns success.
-        if self.storage.contains_key(&key) {
+    /// Runs write, unless a write carrying the same client supplied id has already
+    /// been applied, in which case the retry is a no-op returning the version token
+    /// the original write produced. Only successful writes are remembered, and only
+    /// the latest ids within a fixed window are.
+    pub fn apply_once<F>(&mut self, id: String, write: F) -> Result<u64, ExecResult>
+    where
+        F: FnOnce(&mut Self) -> Result<u64, ExecResult>,
+    {
+        if let Some(version) = self.applied.get(&id) {
+            return Ok(*version);
+        }
+
+        let version = write(self)?;
+        if self.applied_order.len() == WRITE_ID_WINDOW {
+            if let Some(oldest) = self.applied_order.pop_front() {
+                self.applied.remove(&oldest);
+            }
+        }
+        self.applied.insert(id.clone(), version);
+        self.applied_order.push_back(id);
+
+        Ok(version)
+    }
+
+    /// Registers handler to be run on every write to a key for which matches
+    /// returns true. Handlers run right after the write, as part of the same
+    /// operation, and may themselves write to the store. Writes made by handlers
+    /// don't run any triggers, so that handlers can't recursively trigger themselves.
+    pub fn add_trigger<M, H>(&mut self, matches: M, handler: H)
+    where
+        M: Fn(&A) -> bool + Send + 'static,
+        H: Fn(&mut Self, Event<A, B>) + Send + 'static,
+    {
+        self.triggers.push(Trigger {
+            matches: Box::new(matches),
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Defines key as a view, holding the value computed from the store by compute,
+    /// which is kept up to date by recomputing it on every write to keys for which
+    /// sources returns true. The view can be read like any other key.
+    pub fn add_view<M, C>(&mut self, key: A, sources: M, compute: C)
+    where
+        A: Send + 'static,
+        M: Fn(&A) -> bool + Send + 'static,
+        C: Fn(&Self) -> B + Send + 'static,
+    {
+        let value = compute(self);
+        self.write(key.clone(), value);
+        self.add_trigger(sources, move |store, _| {
+            let value = compute(store);
+            store.write(key.clone(), value);
+        });
+    }
+
+    /// Runs all triggers matching the key written to in event.
+    fn fire(&mut self, event: Event<A, B>) {
+        // Triggers are taken out of the store while running, so that handlers
+        // can borrow it and their own writes don't run triggers.
+        let mut triggers = std::mem::take(&mut self.triggers);
+        for trigger in &triggers {
+            if (trigger.matches)(event.key()) {
+                (trigger.handler)(self, event);
+            }
+        }
+        // Keep triggers registered by handlers while running.
+        triggers.append(&mut self.triggers);
+        self.triggers = triggers;
+    }
+
+    /// Associates key with value under a fresh version token, which is returned.
+    fn write(&mut self, key: A, value: B) -> u64 {
+        self.version += 1;
+        let version = self.version;
+        let now = SystemTime::now();
+        // Replacing a value keeps the metadata describing the key's lifetime.
+        let (created_at, accesses) = match self.storage.get(&key) {
+            Some(entry) => (entry.created_at, entry.accesses.get()),
+            None => (now, 0),
+        };
+        self.storage.insert(
+            key.clone(),
+            Entry {
+                value: value.clone(),
+                version,
+                created_at,
+                updated_at: now,
+                accesses: Cell::new(accesses),
+            },
+        );
+        self.fire(Event::Set(&key, &value));
+
+        version
+    }
+
+    /// Operates HashMap::insert(), if mode allows it, returning the value replaced, if any.
+    /// Fails without writing, if mode doesn't allow it.
+    pub fn set(&mut self, key: A, value: B, mode: SetMode) -> Result<Option<B>, ExecResult> {
+        let previous = self.storage.get(&key).map(|entry| entry.value.clone());
+        match (mode, &previous) {
+            (SetMode::IfAbsent, Some(_)) => {
+                eprintln!("Error: Key already associated with another value.");
+                Err(ExecResult::Failed)
+            }
+            (SetMode::IfPresent, None) => {
+                eprintln!("Error: No value associated with key.");
+                Err(ExecResult::Failed)
+            }
+            _ => {
+                self.write(key, value);
+                Ok(previous)
+            }
+        }
+    }
+
+    /// Stores every key-value pair in pairs, all at once, as set() would with
+    /// SetMode::IfAbsent. Fails without storing any, if one of the keys is
+    /// already associated with a value or repeated.
+    pub fn set_many(&mut self, pairs: Vec<(A, B)>) -> ExecResult {
+        let mut keys = HashSet::with_capacity(pairs.len());
+        if pairs
+            .iter()
+            .any(|(key, _)| self.storage.contains_key(key) || !keys.insert(key))
+        {
             eprintln!("Error: Key already associated with another value.");
-            ExecResult::Failed
-        } else {
-            self.storage.insert(key, value);
-            ExecResult::Success
+            return ExecResult::Failed;
+        }
+        for (key, value) in pairs {
+            self.write(key, value);
+        }
+
+        ExecResult::Success
+    }
+
+    /// Replaces the value associated with key, only if the key's current version
+    /// token matches version, returning the new version token on success.
+    pub fn set_if_version(&mut self, key: A, value: B, version: u64) -> Result<u64, ExecResult> {
+        match self.storage.get(&key) {
+            Some(entry) if entry.version == version => Ok(self.write(key, value)),
+            _ => {
+                eprintln!("Error: Key not associated with a value of the given version.");
+                Err(ExecResult::Failed)
+            }
         }
     }
 
@@ -44,16 +344,65 @@ impl<A: Hash + Eq, B: Clone> Store<A, B> {
     pub fn get(&self, key: A) -> Result<B, ExecResult> {
         match self.storage.get(&key) {
             None => Err(ExecResult::Failed),
-            Some(s) => Ok(B::from(s.clone())),
+            Some(s) => {
+                s.accesses.set(s.accesses.get() + 1);
+                Ok(B::from(s.value.clone()))
+            }
         }
     }
 
+    /// Same as get() for every key in keys, with None for keys that aren't
+    /// associated with a value.
+    pub fn get_many(&self, keys: Vec<A>) -> Vec<Option<B>> {
+        keys.into_iter().map(|key| self.get(key).ok()).collect()
+    }
+
+    /// Same as get(), but also returns the version token of the value.
+    pub fn get_versioned(&self, key: A) -> Result<(B, u64), ExecResult> {
+        match self.storage.get(&key) {
+            None => Err(ExecResult::Failed),
+            Some(s) => {
+                s.accesses.set(s.accesses.get() + 1);
+                Ok((s.value.clone(), s.version))
+            }
+        }
+    }
+
+    /// Returns metadata recorded for key, failing if it isn't associated with a value.
+    pub fn stat(&self, key: A) -> Result<Metadata, ExecResult> {
+        match self.storage.get(&key) {
+            None => Err(ExecResult::Failed),
+            Some(s) => Ok(Metadata {
+                created_at: s.created_at,
+                updated_at: s.updated_at,
+                accesses: s.accesses.get(),
+                version: s.version,
+            }),
+        }
+    }
+
+    /// Number of keys associated with a value, counted without visiting them.
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Whether no key is associated with a value.
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    /// Iterates over all key-value pairs in the store, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&A, &B)> {
+        self.storage.iter().map(|(key, entry)| (key, &entry.value))
+    }
+
     /// Operates HashMap::remove() and fails if the key-value pair
     /// doesn't exist, else deletes it and returns success.
     pub fn del(&mut self, key: A) -> ExecResult {
         match self.storage.remove(&key) {
             Some(val) => {
                 println!("Deleted: Key -> Value mapping.");
+                self.fire(Event::Del(&key));
                 ExecResult::Success
             }
             None => {
@@ -62,6 +411,522 @@ impl<A: Hash + Eq, B: Clone> Store<A, B> {
             }
         }
     }
+
+    /// Moves the value associated with from, along with its metadata, to the key to,
+    /// under a fresh version token, all in one operation. Any value associated with to
+    /// is replaced, unless overwrite is false, in which case renaming fails instead.
+    /// Fails if from isn't associated with a value. Renaming a key to itself leaves it
+    /// untouched, failing unless overwrite is true, as the target is in use.
+    pub fn rename(&mut self, from: A, to: A, overwrite: bool) -> ExecResult {
+        if let Some(result) = self.check_transfer(&from, &to, overwrite, "rename") {
+            return result;
+        }
+        let mut entry = match self.storage.remove(&from) {
+            Some(entry) => entry,
+            None => return ExecResult::Failed,
+        };
+        self.version += 1;
+        entry.version = self.version;
+        entry.updated_at = SystemTime::now();
+        let value = entry.value.clone();
+        self.storage.insert(to.clone(), entry);
+        self.fire(Event::Del(&from));
+        self.fire(Event::Set(&to, &value));
+
+        ExecResult::Success
+    }
+
+    /// Associates the key to with a copy of the value associated with from, as a new
+    /// key with metadata of its own, all in one operation. Any value associated with to
+    /// is replaced, unless overwrite is false, in which case copying fails instead.
+    /// Fails if from isn't associated with a value. Copying a key to itself leaves it
+    /// untouched, as with rename().
+    pub fn copy(&mut self, from: A, to: A, overwrite: bool) -> ExecResult {
+        if let Some(result) = self.check_transfer(&from, &to, overwrite, "copy") {
+            return result;
+        }
+        let value = match self.storage.get(&from) {
+            Some(entry) => entry.value.clone(),
+            None => return ExecResult::Failed,
+        };
+        // Copies start their lifetime anew, rather than continuing that of a replaced value.
+        self.storage.remove(&to);
+        self.write(to, value);
+
+        ExecResult::Success
+    }
+
+    /// Decides the result of moving or copying the value associated with from to the key
+    /// to, as op, without doing so, None if the value is to be moved or copied. Missing
+    /// values are reported first, so that callers can tell the two failures apart.
+    fn check_transfer(&self, from: &A, to: &A, overwrite: bool, op: &str) -> Option<ExecResult> {
+        if !self.storage.contains_key(from) {
+            eprintln!("Error: Can't {}, as no value associated with key.", op);
+            return Some(ExecResult::Failed);
+        }
+        if !overwrite && self.storage.contains_key(to) {
+            eprintln!("Error: Key already associated with another value.");
+            return Some(ExecResult::Failed);
+        }
+        match from == to {
+            true => Some(ExecResult::Success),
+            false => None,
+        }
+    }
+
+    /// Deletes the key-value pair, returning the value deleted, and fails if
+    /// the key isn't associated with a value.
+    pub fn take(&mut self, key: A) -> Result<B, ExecResult> {
+        match self.storage.remove(&key) {
+            Some(entry) => {
+                self.fire(Event::Del(&key));
+                Ok(entry.value)
+            }
+            None => Err(ExecResult::Failed),
+        }
+    }
+
+    /// Deletes every key in keys that is associated with a value, returning
+    /// the number of keys deleted.
+    pub fn del_many(&mut self, keys: Vec<A>) -> usize {
+        let mut deleted = 0;
+        for key in keys {
+            if self.storage.remove(&key).is_some() {
+                self.fire(Event::Del(&key));
+                deleted += 1;
+            }
+        }
+
+        deleted
+    }
+
+    /// Removes all key-value pairs at once, returning them to be freed by the caller.
+    /// Triggers aren't run, views are removed too and only recomputed on the next
+    /// write to their sources. Version tokens keep counting up, so they are never
+    /// reused, and write IDs stay remembered.
+    pub fn clear(&mut self) -> Cleared<A, B> {
+        Cleared(std::mem::take(&mut self.storage))
+    }
+
+    /// Deletes all keys for which matches returns true and that haven't been
+    /// written to for longer than max_age, returning the number of keys deleted.
+    pub fn expire<M: Fn(&A) -> bool>(&mut self, matches: M, max_age: Duration) -> usize {
+        let now = SystemTime::now();
+        let expired: Vec<A> = self
+            .storage
+            .iter()
+            .filter(|(key, entry)| {
+                matches(key)
+                    && now
+                        .duration_since(entry.updated_at)
+                        .is_ok_and(|age| age > max_age)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.storage.remove(key);
+            self.fire(Event::Del(key));
+        }
+
+        expired.len()
+    }
+}
+
+/// Number of keys sharing a prefix and their size.
+#[cfg_attr(test, derive(PartialEq, Debug))]
+pub struct PrefixStats {
+    pub keys: u64,
+    /// Total size in bytes of the keys and their values.
+    pub bytes: u64,
+}
+
+/// Marks the start of a snapshot, followed by the format version.
+const SNAPSHOT_MAGIC: &[u8; 4] = b"KVDB";
+/// Format 2 added the checksum trailer, format 1 snapshots having none.
+const SNAPSHOT_FORMAT: u32 = 2;
+
+/// Snapshots can only be taken of, and keyspaces only analyzed for, stores
+/// whose keys and values are byte strings.
+impl<A, B> Store<A, B>
+where
+    A: Hash + Eq + Clone + AsRef<[u8]> + From<Vec<u8>>,
+    B: Clone + AsRef<[u8]> + From<Vec<u8>>,
+{
+    /// Computes a digest of all key-value pairs, independent of the order they were
+    /// written in and of any metadata, so that stores holding exactly the same data,
+    /// e.g. a replica or a restored backup and its source, have the same digest.
+    /// The digest is meant to detect accidental divergence and isn't cryptographic.
+    /// Fails if still running past deadline, if any.
+    pub fn digest(&self, deadline: Option<Instant>) -> Result<u64, TimedOut> {
+        let mut ordered: BTreeMap<&[u8], &[u8]> = BTreeMap::new();
+        for (i, (key, entry)) in self.storage.iter().enumerate() {
+            check_deadline(deadline, i)?;
+            ordered.insert(key.as_ref(), entry.value.as_ref());
+        }
+        let mut hash = FNV_OFFSET_BASIS;
+        for (key, value) in ordered {
+            // Lengths are hashed too, so that data can't shift between keys and values.
+            hash = fnv1a(hash, &(key.len() as u64).to_be_bytes());
+            hash = fnv1a(hash, key);
+            hash = fnv1a(hash, &(value.len() as u64).to_be_bytes());
+            hash = fnv1a(hash, value);
+        }
+
+        Ok(hash)
+    }
+
+    /// Adds delta to the integer the value associated with key holds in decimal, taken
+    /// to be 0 if there is none, and stores the result, which is returned. Fails, leaving
+    /// the value as is, if it isn't an integer or the result would overflow.
+    pub fn incr_by(&mut self, key: A, delta: i64) -> Result<i64, ExecResult> {
+        let current = match self.storage.get(&key) {
+            None => 0,
+            Some(entry) => parse_int(entry.value.as_ref()).ok_or(ExecResult::Failed)?,
+        };
+        let value = current.checked_add(delta).ok_or(ExecResult::Failed)?;
+        self.write(key, B::from(value.to_string().into_bytes()));
+
+        Ok(value)
+    }
+
+    /// Returns the length in bytes of the value associated with key, without copying
+    /// the value, and fails if the key isn't associated with a value.
+    pub fn value_len(&self, key: A) -> Result<usize, ExecResult> {
+        match self.storage.get(&key) {
+            None => Err(ExecResult::Failed),
+            Some(s) => {
+                s.accesses.set(s.accesses.get() + 1);
+                Ok(s.value.as_ref().len())
+            }
+        }
+    }
+
+    /// Returns the 64-bit FNV-1a hash of the value associated with key, without copying
+    /// the value, and fails if the key isn't associated with a value.
+    pub fn value_fnv1a(&self, key: A) -> Result<u64, ExecResult> {
+        match self.storage.get(&key) {
+            None => Err(ExecResult::Failed),
+            Some(s) => {
+                s.accesses.set(s.accesses.get() + 1);
+                Ok(fnv1a(FNV_OFFSET_BASIS, s.value.as_ref()))
+            }
+        }
+    }
+
+    /// Returns the type of the value associated with key, None if there is none.
+    pub fn type_of(&self, key: A) -> Option<ValueType> {
+        self.storage
+            .get(&key)
+            .map(|entry| match parse_int(entry.value.as_ref()) {
+                Some(_) => ValueType::Int,
+                None => ValueType::String,
+            })
+    }
+
+    /// Appends suffix to the value associated with key, which is created holding only
+    /// suffix if there is none, returning the length of the value. The entry is updated
+    /// in place, keeping its metadata, as values themselves can't be grown.
+    pub fn append(&mut self, key: A, suffix: &[u8]) -> usize {
+        let entry = match self.storage.get_mut(&key) {
+            Some(entry) => entry,
+            None => {
+                self.write(key, B::from(suffix.to_vec()));
+                return suffix.len();
+            }
+        };
+        let mut value = Vec::with_capacity(entry.value.as_ref().len() + suffix.len());
+        value.extend_from_slice(entry.value.as_ref());
+        value.extend_from_slice(suffix);
+        let len = value.len();
+        self.version += 1;
+        entry.value = B::from(value);
+        entry.version = self.version;
+        entry.updated_at = SystemTime::now();
+        let value = entry.value.clone();
+        self.fire(Event::Set(&key, &value));
+
+        len
+    }
+
+    /// Returns all keys matching the glob pattern, see pattern::matches(), ordered bytewise.
+    /// Fails if still running past deadline, if any.
+    pub fn keys(&self, pattern: &[u8], deadline: Option<Instant>) -> Result<Vec<A>, TimedOut> {
+        let mut keys = vec![];
+        for (i, key) in self.storage.keys().enumerate() {
+            check_deadline(deadline, i)?;
+            if crate::pattern::matches(pattern, key.as_ref()) {
+                keys.push(key.clone());
+            }
+        }
+        keys.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        Ok(keys)
+    }
+
+    /// Returns up to count keys matching the glob pattern that follow cursor in bytewise
+    /// order, along with the cursor to continue from. Scans start from, and end on, cursor
+    /// `0`, keys stored throughout a scan are returned exactly once, keys written or deleted
+    /// during it may or may not be. Unlike keys(), only a page of keys is held at a time,
+    /// count being capped at MAX_SCAN_COUNT. Fails on cursors that weren't returned by scan(),
+    /// and if still running past deadline, if any.
+    pub fn scan(
+        &self,
+        cursor: &str,
+        pattern: &[u8],
+        count: usize,
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<A>, String), ScanError> {
+        let after = decode_cursor(cursor).ok_or(ScanError::InvalidCursor)?;
+        let count = count.clamp(1, MAX_SCAN_COUNT);
+        // Keeps the first count keys following cursor, the greatest on top.
+        let mut page = BinaryHeap::with_capacity(count.min(self.storage.len()) + 1);
+        let mut more = false;
+        for (i, key) in self.storage.keys().enumerate() {
+            check_deadline(deadline, i)?;
+            let key = key.as_ref();
+            if after.as_ref().is_some_and(|after| key <= after.as_slice())
+                || !crate::pattern::matches(pattern, key)
+            {
+                continue;
+            }
+            page.push(key);
+            if page.len() > count {
+                page.pop();
+                more = true;
+            }
+        }
+
+        let page = page.into_sorted_vec();
+        let cursor = match (more, page.last()) {
+            (true, Some(last)) => encode_cursor(last),
+            _ => "0".to_string(),
+        };
+        let keys = page.into_iter().map(|key| A::from(key.to_vec())).collect();
+
+        Ok((keys, cursor))
+    }
+
+    /// Groups keys by their prefix up to and including the depth-th delimiter, returning
+    /// the number of keys and their size per prefix, ordered by prefix. Keys with fewer
+    /// delimiters are grouped by the whole key. Fails if still running past deadline, if any.
+    pub fn analyze_prefixes(
+        &self,
+        delimiter: u8,
+        depth: usize,
+        deadline: Option<Instant>,
+    ) -> Result<BTreeMap<Vec<u8>, PrefixStats>, TimedOut> {
+        let mut prefixes = BTreeMap::new();
+        for (i, (key, entry)) in self.storage.iter().enumerate() {
+            check_deadline(deadline, i)?;
+            let key = key.as_ref();
+            let end = match depth {
+                0 => 0,
+                _ => key
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, b)| **b == delimiter)
+                    .nth(depth - 1)
+                    .map_or(key.len(), |(i, _)| i + 1),
+            };
+            let stats = prefixes
+                .entry(key[..end].to_vec())
+                .or_insert(PrefixStats { keys: 0, bytes: 0 });
+            stats.keys += 1;
+            stats.bytes += (key.len() + entry.value.as_ref().len()) as u64;
+        }
+
+        Ok(prefixes)
+    }
+
+    /// Writes a snapshot of all key-value pairs, along with their version tokens and
+    /// timestamps, into writer, followed by a checksum of it. Triggers and access counts
+    /// aren't part of snapshots.
+    pub fn save<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = Checksummed::new(writer);
+        writer.write_all(SNAPSHOT_MAGIC)?;
+        writer.write_all(&SNAPSHOT_FORMAT.to_be_bytes())?;
+        writer.write_all(&self.version.to_be_bytes())?;
+        // The number of entries lets load() tell a complete snapshot from a truncated one.
+        writer.write_all(&(self.storage.len() as u64).to_be_bytes())?;
+        for (key, entry) in &self.storage {
+            writer.write_all(&entry.version.to_be_bytes())?;
+            writer.write_all(&to_millis(entry.created_at).to_be_bytes())?;
+            writer.write_all(&to_millis(entry.updated_at).to_be_bytes())?;
+            write_bytes(&mut writer, key.as_ref())?;
+            write_bytes(&mut writer, entry.value.as_ref())?;
+        }
+        // The checksum lets load() tell a complete snapshot from one with corrupted bytes.
+        writer.inner.write_all(&writer.hash.to_be_bytes())
+    }
+
+    /// Creates a Storage Engine from a snapshot written by save(), read from reader.
+    /// Fails with InvalidData if its checksum doesn't match, or if it is of another format.
+    pub fn load<R: Read>(reader: R) -> io::Result<Self> {
+        let mut reader = Checksummed::new(reader);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        let format = read_u32(&mut reader)?;
+        if &magic != SNAPSHOT_MAGIC || format != SNAPSHOT_FORMAT {
+            let e = match format {
+                1 if &magic == SNAPSHOT_MAGIC => {
+                    "Snapshots of format 1 have no checksum, and are no longer supported"
+                }
+                _ => "Not a snapshot of a supported format",
+            };
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+
+        let mut store = Self::new();
+        store.version = read_u64(&mut reader)?;
+        // A truncated snapshot fails with UnexpectedEof while reading entries.
+        for _ in 0..read_u64(&mut reader)? {
+            let version = read_u64(&mut reader)?;
+            let created_at = from_millis(read_u64(&mut reader)?);
+            let updated_at = from_millis(read_u64(&mut reader)?);
+            let key = read_bytes(&mut reader)?;
+            let value = read_bytes(&mut reader)?;
+            store.storage.insert(
+                A::from(key),
+                Entry {
+                    value: B::from(value),
+                    version,
+                    created_at,
+                    updated_at,
+                    accesses: Cell::new(0),
+                },
+            );
+        }
+        let hash = reader.hash;
+        if read_u64(&mut reader.inner)? != hash {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Snapshot checksum mismatch",
+            ));
+        }
+        if reader.inner.read(&mut [0])? != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected data after the end of the snapshot",
+            ));
+        }
+
+        Ok(store)
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Continues a 64-bit FNV-1a hash over bytes.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash
+}
+
+/// Wraps a reader or writer, hashing all bytes read from or written to it with FNV-1a.
+struct Checksummed<T> {
+    inner: T,
+    hash: u64,
+}
+
+impl<T> Checksummed<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl<R: Read> Read for Checksummed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.hash = fnv1a(self.hash, &buf[..len]);
+
+        Ok(len)
+    }
+}
+
+impl<W: Write> Write for Checksummed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.hash = fnv1a(self.hash, &buf[..len]);
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The integer value holds in decimal, if any.
+fn parse_int(value: &[u8]) -> Option<i64> {
+    std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+/// Cursor continuing a scan after key, which it holds in hex.
+fn encode_cursor(key: &[u8]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The key a scan continues after, None for cursor `0`, which starts a scan.
+fn decode_cursor(cursor: &str) -> Option<Option<Vec<u8>>> {
+    if cursor == "0" {
+        return Some(None);
+    }
+    if cursor.len() % 2 == 1 {
+        return None;
+    }
+    (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .map(Some)
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn from_millis(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+/// Writes bytes prefixed by their length.
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    let len: u32 = bytes
+        .len()
+        .try_into()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Too long for a snapshot"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; read_u32(reader)? as usize];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+
+    Ok(u64::from_be_bytes(buf))
 }
 
 #[cfg(test)]
@@ -77,14 +942,42 @@ mod tests {
     }
 
     #[test]
-    fn test_set_key_in_use() {
+    fn test_set_modes() {
         let mut store = Store::new();
 
-        let result = store.set("key1", "value1");
+        let result = store.set("key1", "value1", SetMode::IfPresent);
+        assert_eq!(result, Err(ExecResult::Failed));
+
+        let result = store.set("key1", "value1", SetMode::IfAbsent);
+        assert_eq!(result, Ok(None));
+
+        let result = store.set("key1", "value2", SetMode::IfAbsent);
+        assert_eq!(result, Err(ExecResult::Failed));
+        assert_eq!(store.get("key1"), Ok("value1"));
+
+        let result = store.set("key1", "value2", SetMode::IfPresent);
+        assert_eq!(result, Ok(Some("value1")));
+
+        let result = store.set("key1", "value3", SetMode::Always);
+        assert_eq!(result, Ok(Some("value2")));
+        assert_eq!(store.get("key1"), Ok("value3"));
+    }
+
+    #[test]
+    fn test_set_many_is_all_or_nothing() {
+        let mut store = Store::new();
+
+        let result = store.set_many(vec![("key1", "value1"), ("key2", "value2")]);
         assert_eq!(result, ExecResult::Success);
+        assert_eq!(store.get("key2"), Ok("value2"));
 
-        let result = store.set("key1", "value1");
+        let result = store.set_many(vec![("key3", "value3"), ("key1", "value1")]);
         assert_eq!(result, ExecResult::Failed);
+        assert_eq!(store.get("key3"), Err(ExecResult::Failed));
+
+        let result = store.set_many(vec![("key4", "value4"), ("key4", "value5")]);
+        assert_eq!(result, ExecResult::Failed);
+        assert_eq!(store.get("key4"), Err(ExecResult::Failed));
     }
 
     #[test]
@@ -95,12 +988,167 @@ mod tests {
         assert_eq!(result, ExecResult::Failed);
     }
 
+    #[test]
+    fn test_take_returns_deleted_value() {
+        let mut store = Store::new();
+        store.set("key1", "value1", SetMode::Always).unwrap();
+
+        assert_eq!(store.take("key1"), Ok("value1"));
+        assert_eq!(store.get("key1"), Err(ExecResult::Failed));
+        assert_eq!(store.take("key1"), Err(ExecResult::Failed));
+    }
+
+    #[test]
+    fn test_del_many_counts_deleted_keys() {
+        let mut store = Store::new();
+        store.set_many(vec![("key1", "value1"), ("key2", "value2")]);
+
+        let result = store.del_many(vec!["key1", "key3", "key2", "key1"]);
+        assert_eq!(result, 2);
+        assert_eq!(store.get("key2"), Err(ExecResult::Failed));
+    }
+
+    #[test]
+    fn test_rename_moves_values() {
+        let mut store: Store<String, String> = Store::new();
+        store
+            .set("old".to_string(), "value".to_string(), SetMode::Always)
+            .unwrap();
+        store
+            .set("taken".to_string(), "other".to_string(), SetMode::Always)
+            .unwrap();
+
+        assert_eq!(
+            store.rename("old".to_string(), "taken".to_string(), false),
+            ExecResult::Failed
+        );
+        assert_eq!(
+            store.rename("old".to_string(), "new".to_string(), false),
+            ExecResult::Success
+        );
+        assert_eq!(store.get("old".to_string()), Err(ExecResult::Failed));
+        assert_eq!(
+            store.rename("new".to_string(), "taken".to_string(), true),
+            ExecResult::Success
+        );
+        assert_eq!(store.get("taken".to_string()), Ok("value".to_string()));
+        assert_eq!(
+            store.rename("missing".to_string(), "other".to_string(), true),
+            ExecResult::Failed
+        );
+        for overwrite in &[false, true] {
+            assert_eq!(
+                store.rename("missing".to_string(), "taken".to_string(), *overwrite),
+                ExecResult::Failed
+            );
+            assert_eq!(
+                store.copy("missing".to_string(), "taken".to_string(), *overwrite),
+                ExecResult::Failed
+            );
+        }
+        assert_eq!(store.get("taken".to_string()), Ok("value".to_string()));
+    }
+
+    #[test]
+    fn test_transfers_to_same_key() {
+        let mut store: Store<String, String> = Store::new();
+        store
+            .set("key".to_string(), "value".to_string(), SetMode::Always)
+            .unwrap();
+        let version = store.stat("key".to_string()).unwrap().version;
+
+        for overwrite in &[false, true] {
+            let expected = match overwrite {
+                true => ExecResult::Success,
+                false => ExecResult::Failed,
+            };
+            assert_eq!(
+                store.rename("key".to_string(), "key".to_string(), *overwrite),
+                expected
+            );
+            assert_eq!(
+                store.copy("key".to_string(), "key".to_string(), *overwrite),
+                expected
+            );
+        }
+        assert_eq!(store.get("key".to_string()), Ok("value".to_string()));
+        assert_eq!(store.stat("key".to_string()).unwrap().version, version);
+        assert_eq!(
+            store.rename("missing".to_string(), "missing".to_string(), true),
+            ExecResult::Failed
+        );
+    }
+
+    #[test]
+    fn test_copy_duplicates_values() {
+        let mut store: Store<String, String> = Store::new();
+        store
+            .set("src".to_string(), "value".to_string(), SetMode::Always)
+            .unwrap();
+        store
+            .set("dst".to_string(), "other".to_string(), SetMode::Always)
+            .unwrap();
+
+        assert_eq!(
+            store.copy("src".to_string(), "dst".to_string(), false),
+            ExecResult::Failed
+        );
+        assert_eq!(store.get("dst".to_string()), Ok("other".to_string()));
+        assert_eq!(
+            store.copy("src".to_string(), "dst".to_string(), true),
+            ExecResult::Success
+        );
+        assert_eq!(store.get("src".to_string()), Ok("value".to_string()));
+        assert_eq!(store.get("dst".to_string()), Ok("value".to_string()));
+        assert_eq!(
+            store.copy("missing".to_string(), "other".to_string(), true),
+            ExecResult::Failed
+        );
+    }
+
+    #[test]
+    fn test_len_counts_keys() {
+        let mut store = Store::new();
+        assert!(store.is_empty());
+
+        store.set("key1", "value1", SetMode::Always).unwrap();
+        store.set("key2", "value2", SetMode::Always).unwrap();
+        store.set("key1", "value3", SetMode::Always).unwrap();
+        assert_eq!(store.len(), 2);
+
+        store.del("key2");
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_clear_removes_all_keys() {
+        let mut store = Store::new();
+        store.set("key1", "value1", SetMode::Always).unwrap();
+        store.set("key2", "value2", SetMode::Always).unwrap();
+        let version = store.stat("key2").unwrap().version;
+
+        assert_eq!(store.clear().len(), 2);
+        assert!(store.is_empty());
+        store.set("key1", "value1", SetMode::Always).unwrap();
+        assert!(store.stat("key1").unwrap().version > version);
+    }
+
+    #[test]
+    fn test_get_many_marks_missing_keys() {
+        let mut store = Store::new();
+        store.set("key1", "value1", SetMode::Always).unwrap();
+
+        let result = store.get_many(vec!["key1", "key2", "key1"]);
+        assert_eq!(result, vec![Some("value1"), None, Some("value1")]);
+        assert_eq!(store.stat("key1").unwrap().accesses, 2);
+    }
+
     #[test]
     fn test_flow_ok() {
         let mut store = Store::new();
 
-        let result = store.set("key1", "value1");
-        assert_eq!(result, ExecResult::Success);
+        let result = store.set("key1", "value1", SetMode::Always);
+        assert_eq!(result, Ok(None));
 
         let result = store.get("key1");
         assert_eq!(result, Ok("value1"));
@@ -111,4 +1159,447 @@ mod tests {
         let result = store.get("key1");
         assert_eq!(result, Err(ExecResult::Failed));
     }
+
+    #[test]
+    fn test_set_if_version() {
+        let mut store = Store::new();
+
+        let result = store.set_if_version("key1", "value1", 0);
+        assert_eq!(result, Err(ExecResult::Failed));
+
+        store.set("key1", "value1", SetMode::Always).unwrap();
+        let (_, version) = store.get_versioned("key1").unwrap();
+
+        let result = store.set_if_version("key1", "value2", version);
+        assert!(matches!(result, Ok(v) if v != version));
+        assert_eq!(store.get("key1"), Ok("value2"));
+
+        let result = store.set_if_version("key1", "value3", version);
+        assert_eq!(result, Err(ExecResult::Failed));
+        assert_eq!(store.get("key1"), Ok("value2"));
+    }
+
+    #[test]
+    fn test_apply_once_turns_retries_into_noops() {
+        let mut store = Store::new();
+        let set = |store: &mut Store<&str, &str>| {
+            store.set("key1", "value1", SetMode::IfAbsent)?;
+            store.stat("key1").map(|meta| meta.version)
+        };
+
+        let first = store.apply_once("id1".to_string(), set);
+        assert!(first.is_ok());
+        assert_eq!(store.apply_once("id1".to_string(), set), first);
+        assert_eq!(
+            store.apply_once("id2".to_string(), set),
+            Err(ExecResult::Failed)
+        );
+    }
+
+    #[test]
+    fn test_stat_tracks_metadata() {
+        let mut store = Store::new();
+        assert_eq!(store.stat("key1"), Err(ExecResult::Failed));
+
+        store.set("key1", "value1", SetMode::Always).unwrap();
+        let created = store.stat("key1").unwrap();
+        assert_eq!(created.created_at, created.updated_at);
+        assert_eq!(created.accesses, 0);
+
+        store.get("key1").unwrap();
+        store.get("key1").unwrap();
+        store
+            .set_if_version("key1", "value2", created.version)
+            .unwrap();
+        let updated = store.stat("key1").unwrap();
+        assert_eq!(updated.created_at, created.created_at);
+        assert!(updated.updated_at >= created.updated_at);
+        assert_eq!(updated.accesses, 2);
+        assert_ne!(updated.version, created.version);
+    }
+
+    #[test]
+    fn test_expire_old_matching_keys() {
+        let mut store = Store::new();
+        store.set("events:1", "value", SetMode::Always).unwrap();
+        store.set("other", "value", SetMode::Always).unwrap();
+        assert_eq!(
+            store.expire(|key| key.starts_with("events:"), Duration::from_secs(60)),
+            0
+        );
+
+        std::thread::sleep(Duration::from_millis(10));
+        store.set("events:2", "value", SetMode::Always).unwrap();
+        assert_eq!(
+            store.expire(|key| key.starts_with("events:"), Duration::from_millis(5)),
+            1
+        );
+        assert_eq!(store.get("events:1"), Err(ExecResult::Failed));
+        assert_eq!(store.get("events:2"), Ok("value"));
+        assert_eq!(store.get("other"), Ok("value"));
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store
+            .set(b"key1".to_vec(), b"value1".to_vec(), SetMode::Always)
+            .unwrap();
+        store
+            .set(b"key2".to_vec(), vec![], SetMode::Always)
+            .unwrap();
+        let stat = store.stat(b"key1".to_vec()).unwrap();
+
+        let mut snapshot = vec![];
+        store.save(&mut snapshot).unwrap();
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::load(&snapshot[..]).unwrap();
+
+        assert_eq!(store.get(b"key1".to_vec()), Ok(b"value1".to_vec()));
+        assert_eq!(store.get(b"key2".to_vec()), Ok(vec![]));
+        let loaded = store.stat(b"key1".to_vec()).unwrap();
+        assert_eq!(loaded.version, stat.version);
+        assert_eq!(to_millis(loaded.created_at), to_millis(stat.created_at));
+
+        // Tokens handed out before the snapshot aren't reused.
+        store
+            .set(b"key3".to_vec(), vec![], SetMode::Always)
+            .unwrap();
+        assert!(store.stat(b"key3".to_vec()).unwrap().version > stat.version);
+    }
+
+    #[test]
+    fn test_load_rejects_truncated_snapshot() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store
+            .set(b"key1".to_vec(), b"value1".to_vec(), SetMode::Always)
+            .unwrap();
+        store
+            .set(b"key2".to_vec(), b"value2".to_vec(), SetMode::Always)
+            .unwrap();
+
+        let mut snapshot = vec![];
+        store.save(&mut snapshot).unwrap();
+        for len in 0..snapshot.len() {
+            let result: io::Result<Store<Vec<u8>, Vec<u8>>> = Store::load(&snapshot[..len]);
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_analyze_prefixes() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store
+            .set(b"user:1:name".to_vec(), b"alice".to_vec(), SetMode::Always)
+            .unwrap();
+        store
+            .set(b"user:2:name".to_vec(), b"bob".to_vec(), SetMode::Always)
+            .unwrap();
+        store
+            .set(b"session:ab".to_vec(), b"1".to_vec(), SetMode::Always)
+            .unwrap();
+        store
+            .set(b"config".to_vec(), b"{}".to_vec(), SetMode::Always)
+            .unwrap();
+
+        let prefixes: Vec<_> = store
+            .analyze_prefixes(b':', 1, None)
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            prefixes,
+            vec![
+                (b"config".to_vec(), PrefixStats { keys: 1, bytes: 8 }),
+                (b"session:".to_vec(), PrefixStats { keys: 1, bytes: 11 }),
+                (b"user:".to_vec(), PrefixStats { keys: 2, bytes: 30 }),
+            ]
+        );
+
+        let prefixes = store.analyze_prefixes(b':', 2, None).unwrap();
+        assert_eq!(prefixes[&b"user:1:".to_vec()].keys, 1);
+        assert_eq!(prefixes[&b"session:ab".to_vec()].keys, 1);
+    }
+
+    #[test]
+    fn test_incr_by_adjusts_integers() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        assert_eq!(store.incr_by(b"hits".to_vec(), 1), Ok(1));
+        assert_eq!(store.incr_by(b"hits".to_vec(), 10), Ok(11));
+        assert_eq!(store.incr_by(b"hits".to_vec(), -12), Ok(-1));
+        assert_eq!(store.get(b"hits".to_vec()), Ok(b"-1".to_vec()));
+
+        store
+            .set(b"name".to_vec(), b"alice".to_vec(), SetMode::Always)
+            .unwrap();
+        assert_eq!(store.incr_by(b"name".to_vec(), 1), Err(ExecResult::Failed));
+        store
+            .set(
+                b"max".to_vec(),
+                i64::MAX.to_string().into_bytes(),
+                SetMode::Always,
+            )
+            .unwrap();
+        assert_eq!(store.incr_by(b"max".to_vec(), 1), Err(ExecResult::Failed));
+        assert_eq!(
+            store.get(b"max".to_vec()),
+            Ok(i64::MAX.to_string().into_bytes())
+        );
+    }
+
+    #[test]
+    fn test_append_creates_or_extends_values() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        assert_eq!(store.append(b"log".to_vec(), b"a"), 1);
+        let version = store.stat(b"log".to_vec()).unwrap().version;
+        assert_eq!(store.append(b"log".to_vec(), b"bc"), 3);
+        assert_eq!(store.get(b"log".to_vec()), Ok(b"abc".to_vec()));
+        assert!(store.stat(b"log".to_vec()).unwrap().version > version);
+    }
+
+    #[test]
+    fn test_value_len() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store
+            .set(
+                b"name".to_vec(),
+                "émile".as_bytes().to_vec(),
+                SetMode::Always,
+            )
+            .unwrap();
+
+        assert_eq!(store.value_len(b"name".to_vec()), Ok(6));
+        assert_eq!(store.stat(b"name".to_vec()).unwrap().accesses, 1);
+        assert_eq!(
+            store.value_len(b"missing".to_vec()),
+            Err(ExecResult::Failed)
+        );
+    }
+
+    #[test]
+    fn test_type_of_values() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store.incr_by(b"hits".to_vec(), 1).unwrap();
+        store
+            .set(b"name".to_vec(), b"alice".to_vec(), SetMode::Always)
+            .unwrap();
+
+        assert_eq!(store.type_of(b"hits".to_vec()), Some(ValueType::Int));
+        assert_eq!(store.type_of(b"name".to_vec()), Some(ValueType::String));
+        assert_eq!(store.type_of(b"missing".to_vec()), None);
+    }
+
+    #[test]
+    fn test_keys_matching_pattern() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store
+            .set(b"user:2".to_vec(), b"bob".to_vec(), SetMode::Always)
+            .unwrap();
+        store
+            .set(b"user:1".to_vec(), b"alice".to_vec(), SetMode::Always)
+            .unwrap();
+        store
+            .set(b"session:ab".to_vec(), b"1".to_vec(), SetMode::Always)
+            .unwrap();
+
+        assert_eq!(
+            store.keys(b"user:*", None),
+            Ok(vec![b"user:1".to_vec(), b"user:2".to_vec()])
+        );
+        assert_eq!(store.keys(b"*", None).unwrap().len(), 3);
+        assert!(store.keys(b"user", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scan_pages_through_matching_keys() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        for i in 0..5 {
+            store
+                .set(
+                    format!("user:{}", i).into_bytes(),
+                    b"".to_vec(),
+                    SetMode::Always,
+                )
+                .unwrap();
+        }
+        store
+            .set(b"session:ab".to_vec(), b"1".to_vec(), SetMode::Always)
+            .unwrap();
+
+        let mut cursor = "0".to_string();
+        let mut pages = vec![];
+        loop {
+            let (keys, next) = store.scan(&cursor, b"user:*", 2, None).unwrap();
+            pages.push(keys);
+            cursor = next;
+            if cursor == "0" {
+                break;
+            }
+        }
+        assert_eq!(
+            pages,
+            vec![
+                vec![b"user:0".to_vec(), b"user:1".to_vec()],
+                vec![b"user:2".to_vec(), b"user:3".to_vec()],
+                vec![b"user:4".to_vec()],
+            ]
+        );
+        assert_eq!(store.scan("0", b"*", 10, None).unwrap().0.len(), 6);
+        assert_eq!(
+            store.scan("not hex", b"*", 10, None),
+            Err(ScanError::InvalidCursor)
+        );
+    }
+
+    #[test]
+    fn test_scan_caps_count() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        for i in 0..MAX_SCAN_COUNT + 1 {
+            store
+                .set(i.to_string().into_bytes(), b"".to_vec(), SetMode::Always)
+                .unwrap();
+        }
+
+        let (keys, cursor) = store.scan("0", b"*", usize::MAX, None).unwrap();
+        assert_eq!(keys.len(), MAX_SCAN_COUNT);
+        assert_ne!(cursor, "0");
+        let (keys, cursor) = store.scan(&cursor, b"*", usize::MAX, None).unwrap();
+        assert_eq!((keys.len(), cursor.as_ref()), (1, "0"));
+    }
+
+    #[test]
+    fn test_iterations_time_out_past_deadline() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        for i in 0..DEADLINE_CHECK_INTERVAL + 1 {
+            store
+                .set(i.to_string().into_bytes(), b"".to_vec(), SetMode::Always)
+                .unwrap();
+        }
+
+        let passed = Some(Instant::now());
+        assert_eq!(store.scan("0", b"*", 10, passed), Err(ScanError::TimedOut));
+        assert_eq!(store.keys(b"*", passed), Err(TimedOut));
+        assert_eq!(store.digest(passed), Err(TimedOut));
+        assert!(store.analyze_prefixes(b':', 1, passed).is_err());
+
+        let ahead = Some(Instant::now() + Duration::from_secs(60));
+        assert_eq!(store.scan("0", b"*", 10, ahead).unwrap().0.len(), 10);
+        assert_eq!(store.keys(b"*", ahead).unwrap().len(), store.len());
+        assert_eq!(store.digest(ahead), store.digest(None));
+    }
+
+    #[test]
+    fn test_digest_depends_only_on_data() {
+        let mut store1: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store1
+            .set(b"key1".to_vec(), b"value1".to_vec(), SetMode::Always)
+            .unwrap();
+        store1
+            .set(b"key2".to_vec(), b"value2".to_vec(), SetMode::Always)
+            .unwrap();
+        let mut store2: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store2
+            .set(b"key2".to_vec(), b"value2".to_vec(), SetMode::Always)
+            .unwrap();
+        store2
+            .set(b"key1".to_vec(), b"value1".to_vec(), SetMode::Always)
+            .unwrap();
+        assert_eq!(store1.digest(None), store2.digest(None));
+
+        store2.del(b"key1".to_vec());
+        store2
+            .set(b"key1".to_vec(), b"value".to_vec(), SetMode::Always)
+            .unwrap();
+        assert_ne!(store1.digest(None), store2.digest(None));
+    }
+
+    #[test]
+    fn test_load_rejects_corrupted_snapshot() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store
+            .set(b"key1".to_vec(), b"value1".to_vec(), SetMode::Always)
+            .unwrap();
+
+        let mut snapshot = vec![];
+        store.save(&mut snapshot).unwrap();
+        // Flips a byte of the value, which leaves the snapshot otherwise well-formed.
+        let at = snapshot.len() - 9;
+        snapshot[at] ^= 1;
+        let result: io::Result<Store<Vec<u8>, Vec<u8>>> = Store::load(&snapshot[..]);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        // Format 1 snapshots can't be verified, so aren't loaded.
+        let mut snapshot = vec![];
+        store.save(&mut snapshot).unwrap();
+        snapshot[4..8].copy_from_slice(&1u32.to_be_bytes());
+        let result: io::Result<Store<Vec<u8>, Vec<u8>>> = Store::load(&snapshot[..]);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_value_fnv1a() {
+        let mut store: Store<Vec<u8>, Vec<u8>> = Store::new();
+        store
+            .set(b"key".to_vec(), b"a".to_vec(), SetMode::Always)
+            .unwrap();
+
+        assert_eq!(
+            store.value_fnv1a(b"key".to_vec()),
+            Ok(0xaf63_dc4c_8601_ec8c)
+        );
+        assert_eq!(
+            store.value_fnv1a(b"missing".to_vec()),
+            Err(ExecResult::Failed)
+        );
+    }
+
+    #[test]
+    fn test_load_rejects_garbage() {
+        let result: io::Result<Store<Vec<u8>, Vec<u8>>> = Store::load(&b"garbage!"[..]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_triggers_on_matching_keys() {
+        let mut store = Store::new();
+        store.add_trigger(
+            |key: &&str| key.starts_with("user:"),
+            |store, event| match event {
+                Event::Set(_, value) => {
+                    store.del("last_user");
+                    store.set("last_user", *value, SetMode::Always).unwrap();
+                }
+                Event::Del(_) => {
+                    store.del("last_user");
+                }
+            },
+        );
+
+        store.set("other", "value", SetMode::Always).unwrap();
+        assert_eq!(store.get("last_user"), Err(ExecResult::Failed));
+
+        store.set("user:1", "alice", SetMode::Always).unwrap();
+        assert_eq!(store.get("last_user"), Ok("alice"));
+
+        store.del("user:1");
+        assert_eq!(store.get("last_user"), Err(ExecResult::Failed));
+    }
+
+    #[test]
+    fn test_view_follows_sources() {
+        let mut store: Store<&str, usize> = Store::new();
+        store.set("user:1", 1, SetMode::Always).unwrap();
+        store.add_view(
+            "user_count",
+            |key| key.starts_with("user:"),
+            |store| store.iter().filter(|(k, _)| k.starts_with("user:")).count(),
+        );
+        assert_eq!(store.get("user_count"), Ok(1));
+
+        store.set("user:2", 1, SetMode::Always).unwrap();
+        store.set("other", 1, SetMode::Always).unwrap();
+        assert_eq!(store.get("user_count"), Ok(2));
+
+        store.del("user:1");
+        assert_eq!(store.get("user_count"), Ok(1));
+    }
 }